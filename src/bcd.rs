@@ -0,0 +1,65 @@
+//! Helpers for the packed binary-coded decimal values used throughout
+//! the Q channel.
+
+/// Decodes a packed BCD byte, where each nibble holds one decimal digit.
+/// Returns `None` if either nibble is greater than 9.
+pub fn from_bcd(byte: u8) -> Option<u8> {
+    let high = byte >> 4;
+    let low = byte & 0x0F;
+    if high > 9 || low > 9 {
+        return None;
+    }
+
+    Some(high * 10 + low)
+}
+
+/// Decodes a packed BCD byte without rejecting invalid nibbles, returning
+/// the value along with whether both nibbles were valid. A nibble greater
+/// than 9 is used as-is, so 0x1A decodes to 1 * 10 + 10 = 20; the result
+/// is only an approximation, but is often close enough to be useful when
+/// a single nibble has been corrupted.
+pub fn from_bcd_lenient(byte: u8) -> (u8, bool) {
+    let high = byte >> 4;
+    let low = byte & 0x0F;
+    (high * 10 + low, high <= 9 && low <= 9)
+}
+
+/// Encodes a value from 0 to 99 as packed BCD. Values above 99
+/// don't fit in two digits and are clamped to 99.
+pub fn to_bcd(value: u8) -> u8 {
+    let value = value.min(99);
+    ((value / 10) << 4) | (value % 10)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bcd;
+
+    #[test]
+    fn test_from_bcd() {
+        assert_eq!(Some(0), bcd::from_bcd(0x00));
+        assert_eq!(Some(74), bcd::from_bcd(0x74));
+        assert_eq!(Some(99), bcd::from_bcd(0x99));
+    }
+
+    #[test]
+    fn test_to_bcd() {
+        assert_eq!(0x00, bcd::to_bcd(0));
+        assert_eq!(0x74, bcd::to_bcd(74));
+        assert_eq!(0x99, bcd::to_bcd(150));
+    }
+
+    #[test]
+    fn test_from_bcd_rejects_invalid_nibbles() {
+        assert_eq!(None, bcd::from_bcd(0x1A));
+        assert_eq!(None, bcd::from_bcd(0xA1));
+        assert_eq!(None, bcd::from_bcd(0xAA));
+    }
+
+    #[test]
+    fn test_from_bcd_lenient() {
+        assert_eq!((74, true), bcd::from_bcd_lenient(0x74));
+        assert_eq!((20, false), bcd::from_bcd_lenient(0x1A));
+        assert_eq!((150, false), bcd::from_bcd_lenient(0xF0));
+    }
+}
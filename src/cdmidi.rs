@@ -0,0 +1,167 @@
+//! Decoding of CD-MIDI, which carries a MIDI byte stream in the R-W
+//! channels alongside ordinary audio.
+
+use crate::rw::RwPack;
+
+/// The R-W mode used by CD-MIDI packs.
+pub const MIDI_MODE: u8 = 3;
+
+/// The R-W item used by CD-MIDI packs.
+pub const MIDI_ITEM: u8 = 5;
+
+/// A single MIDI message: a status byte and its data bytes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MidiEvent {
+    pub status: u8,
+    pub data: Vec<u8>,
+}
+
+impl MidiEvent {
+    /// Returns the MIDI channel (0-15) for channel messages.
+    pub fn channel(&self) -> Option<u8> {
+        match self.status {
+            0x80..=0xEF => Some(self.status & 0x0F),
+            _ => None,
+        }
+    }
+}
+
+/// Decodes the MIDI messages carried by a run of R-W packs.
+///
+/// Only packs with mode 3, item 5 are considered. Each carries up to 12
+/// MIDI bytes in its data symbols, with the instruction symbol giving
+/// how many are in use. The bytes from every pack are joined into one
+/// stream, so messages may span packs, and running status is honoured.
+/// Data bytes with no preceding status and a truncated final message
+/// are dropped.
+pub fn parse_packs(packs: &[RwPack]) -> Vec<MidiEvent> {
+    let mut stream = vec![];
+    for pack in packs {
+        if pack.mode() != MIDI_MODE || pack.item() != MIDI_ITEM {
+            continue;
+        }
+        let length = (pack.instruction() as usize).min(12);
+        stream.extend_from_slice(&pack.data_bytes()[..length]);
+    }
+
+    let mut events = vec![];
+    let mut running: Option<u8> = None;
+    let mut current: Option<MidiEvent> = None;
+
+    for byte in stream {
+        if byte >= 0xF8 {
+            // Real-time messages may appear anywhere, even mid-message
+            events.push(MidiEvent {
+                status: byte,
+                data: vec![],
+            });
+            continue;
+        }
+
+        if byte & 0x80 != 0 {
+            if let Some(event) = current.take() {
+                // Only a system exclusive message is ended by a new status
+                if event.status == 0xF0 {
+                    events.push(event);
+                }
+            }
+            if byte == 0xF7 {
+                continue;
+            }
+            running = match byte {
+                0x80..=0xEF => Some(byte),
+                _ => None,
+            };
+            let event = MidiEvent {
+                status: byte,
+                data: vec![],
+            };
+            if data_length(byte) == Some(0) {
+                events.push(event);
+            } else {
+                current = Some(event);
+            }
+            continue;
+        }
+
+        let mut event = match current.take() {
+            Some(event) => event,
+            None => match running {
+                Some(status) => MidiEvent {
+                    status,
+                    data: vec![],
+                },
+                None => continue,
+            },
+        };
+        event.data.push(byte);
+        if data_length(event.status) == Some(event.data.len()) {
+            events.push(event);
+        } else {
+            current = Some(event);
+        }
+    }
+
+    if let Some(event) = current {
+        if event.status == 0xF0 {
+            events.push(event);
+        }
+    }
+
+    events
+}
+
+/// Returns the number of data bytes following a status byte, or `None`
+/// for system exclusive messages, which run until the next status byte.
+fn data_length(status: u8) -> Option<usize> {
+    match status {
+        0x80..=0xBF | 0xE0..=0xEF => Some(2),
+        0xC0..=0xDF => Some(1),
+        0xF0 => None,
+        0xF1 | 0xF3 => Some(1),
+        0xF2 => Some(2),
+        _ => Some(0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cdmidi::{self, MidiEvent};
+    use crate::subcode::testing::pack;
+
+    #[test]
+    fn test_note_on_pack() {
+        let packs = [pack(3, 5, 3, &[0x90, 0x3C, 0x64])];
+        let events = cdmidi::parse_packs(&packs);
+        assert_eq!(
+            vec![MidiEvent {
+                status: 0x90,
+                data: vec![0x3C, 0x64],
+            }],
+            events
+        );
+        assert_eq!(Some(0), events[0].channel());
+    }
+
+    #[test]
+    fn test_running_status_across_packs() {
+        let packs = [
+            pack(3, 5, 4, &[0x91, 0x3C, 0x64, 0x3E]),
+            // A CD+G pack in between is ignored
+            pack(1, 1, 6, &[0xFF, 0xFF]),
+            pack(3, 5, 1, &[0x64]),
+        ];
+        let events = cdmidi::parse_packs(&packs);
+        assert_eq!(2, events.len());
+        assert_eq!(0x91, events[1].status);
+        assert_eq!(vec![0x3E, 0x64], events[1].data);
+    }
+
+    #[test]
+    fn test_truncated_message_is_dropped() {
+        let packs = [pack(3, 5, 2, &[0xC0, 0x05]), pack(3, 5, 2, &[0x80, 0x3C])];
+        let events = cdmidi::parse_packs(&packs);
+        assert_eq!(1, events.len());
+        assert_eq!(0xC0, events[0].status);
+    }
+}
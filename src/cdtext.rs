@@ -0,0 +1,612 @@
+//! Decoding of CD-TEXT, the album and track metadata carried in R-W packs
+//! in the lead-in.
+
+use std::collections::BTreeMap;
+
+use crate::crc;
+use crate::rw::RwPack;
+use crate::subcode::SubcodeData;
+
+pub const PACK_TITLE: u8 = 0x80;
+pub const PACK_PERFORMER: u8 = 0x81;
+pub const PACK_SONGWRITER: u8 = 0x82;
+pub const PACK_COMPOSER: u8 = 0x83;
+pub const PACK_ARRANGER: u8 = 0x84;
+pub const PACK_MESSAGE: u8 = 0x85;
+pub const PACK_DISC_ID: u8 = 0x86;
+pub const PACK_GENRE: u8 = 0x87;
+pub const PACK_SIZE_INFO: u8 = 0x8F;
+
+/// A single 18-byte CD-TEXT pack: a 4-byte header, 12 bytes of text and
+/// a 2-byte CRC.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CdTextPack {
+    pub bytes: [u8; 18],
+}
+
+impl CdTextPack {
+    /// Reads an R-W pack as CD-TEXT. Returns `None` if its pack type
+    /// isn't one of the CD-TEXT types, 0x80 through 0x8F.
+    pub fn from_rw_pack(pack: &RwPack) -> Option<CdTextPack> {
+        let bytes = pack.to_bytes();
+        match bytes[0] {
+            0x80..=0x8F => Some(CdTextPack { bytes }),
+            _ => None,
+        }
+    }
+
+    pub fn pack_type(&self) -> u8 {
+        self.bytes[0]
+    }
+
+    /// Returns the track number the first character of this pack belongs
+    /// to, where 0 refers to the album as a whole.
+    pub fn track(&self) -> u8 {
+        self.bytes[1] & 0x7F
+    }
+
+    /// Returns the pack's sequence number within its block.
+    pub fn sequence(&self) -> u8 {
+        self.bytes[2]
+    }
+
+    /// Returns the block (language) number, 0 through 7.
+    pub fn block(&self) -> u8 {
+        (self.bytes[3] >> 4) & 0b111
+    }
+
+    /// Returns whether the text uses double-byte characters.
+    pub fn is_double_byte(&self) -> bool {
+        self.bytes[3] & 0x80 != 0
+    }
+
+    /// Returns how many characters of the current string precede this
+    /// pack, capped at 15.
+    pub fn character_position(&self) -> u8 {
+        self.bytes[3] & 0x0F
+    }
+
+    pub fn text(&self) -> &[u8] {
+        &self.bytes[4..16]
+    }
+
+    /// Checks the pack's stored CRC, which is inverted like the Q channel's.
+    pub fn crc_valid(&self) -> bool {
+        let stored = u16::from(self.bytes[16]) << 8 | u16::from(self.bytes[17]);
+        !stored == crc::crc16(&self.bytes[..16])
+    }
+}
+
+/// The text fields which can be given for the album and for each track.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CdTextEntry {
+    pub title: Option<String>,
+    pub performer: Option<String>,
+    pub songwriter: Option<String>,
+    pub composer: Option<String>,
+    pub arranger: Option<String>,
+    pub message: Option<String>,
+}
+
+impl CdTextEntry {
+    /// Returns the field stored by text packs of the given type.
+    fn field_mut(&mut self, pack_type: u8) -> Option<&mut Option<String>> {
+        match pack_type {
+            PACK_TITLE => Some(&mut self.title),
+            PACK_PERFORMER => Some(&mut self.performer),
+            PACK_SONGWRITER => Some(&mut self.songwriter),
+            PACK_COMPOSER => Some(&mut self.composer),
+            PACK_ARRANGER => Some(&mut self.arranger),
+            PACK_MESSAGE => Some(&mut self.message),
+            _ => None,
+        }
+    }
+}
+
+/// The contents of the size information (0x8F) packs, which describe
+/// the rest of the CD-TEXT. They're spread across three packs, 36 bytes
+/// in all.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CdTextSizeInfo {
+    /// The character code of the block's text: 0x00 for ISO 8859-1, 0x01
+    /// for ASCII and 0x80 for MS-JIS.
+    pub character_code: u8,
+    pub first_track: u8,
+    pub last_track: u8,
+    /// Copyright flags for the block's fields.
+    pub copyright: u8,
+    /// The number of packs of each type, 0x80 through 0x8F.
+    pub pack_counts: [u8; 16],
+    /// The sequence number of the last pack in each of the eight blocks.
+    pub last_sequences: [u8; 8],
+    /// The language code of each of the eight blocks.
+    pub languages: [u8; 8],
+}
+
+impl CdTextSizeInfo {
+    /// Decodes the 36 bytes of text from the size information packs.
+    /// Returns `None` if fewer than 36 bytes are given.
+    pub fn parse(bytes: &[u8]) -> Option<CdTextSizeInfo> {
+        if bytes.len() < 36 {
+            return None;
+        }
+
+        let mut info = CdTextSizeInfo {
+            character_code: bytes[0],
+            first_track: bytes[1],
+            last_track: bytes[2],
+            copyright: bytes[3],
+            ..CdTextSizeInfo::default()
+        };
+        info.pack_counts.copy_from_slice(&bytes[4..20]);
+        info.last_sequences.copy_from_slice(&bytes[20..28]);
+        info.languages.copy_from_slice(&bytes[28..36]);
+        Some(info)
+    }
+
+    /// Returns the number of packs of the given type, or 0 if it isn't a
+    /// CD-TEXT pack type.
+    pub fn pack_count(&self, pack_type: u8) -> u8 {
+        match pack_type {
+            0x80..=0x8F => self.pack_counts[usize::from(pack_type - 0x80)],
+            _ => 0,
+        }
+    }
+}
+
+/// Decoded CD-TEXT for a disc, with one block per language.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CdText {
+    /// Every block with at least one usable pack, in block order.
+    pub blocks: Vec<CdTextBlock>,
+}
+
+impl CdText {
+    /// Returns the block in the given language, if any.
+    pub fn language(&self, language: u8) -> Option<&CdTextBlock> {
+        self.blocks
+            .iter()
+            .find(|block| block.language == Some(language))
+    }
+}
+
+/// The CD-TEXT of a single block, which holds the text in one language.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CdTextBlock {
+    /// The block number, 0 through 7.
+    pub block: u8,
+    /// The block's language code, as given by the size information.
+    pub language: Option<u8>,
+    pub album: CdTextEntry,
+    pub tracks: BTreeMap<u8, CdTextEntry>,
+    /// The numeric genre code, followed by optional supplementary text.
+    pub genre: Option<(u16, String)>,
+    /// The disc identification, usually a catalog number.
+    pub disc_id: Option<String>,
+    /// The size information, if its packs were present.
+    pub size_info: Option<CdTextSizeInfo>,
+    /// Sequence numbers of packs which were used despite failing their
+    /// CRC. Always empty unless `CdTextOptions::ignore_crc` is set.
+    pub bad_crc_sequences: Vec<u8>,
+}
+
+/// Options controlling how CD-TEXT is decoded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CdTextOptions {
+    /// Use packs which fail their CRC when no valid copy exists, rather
+    /// than dropping them. The packs used are listed in
+    /// `CdTextBlock::bad_crc_sequences`.
+    pub ignore_crc: bool,
+}
+
+/// Decodes CD-TEXT from a collection of packs, such as those read from
+/// the lead-in. Packs are usually repeated many times over, so only the
+/// first CRC-valid copy of each sequence number is used. Packs that fail
+/// their CRC are skipped.
+///
+/// Each of the up to eight blocks is decoded separately. A block's
+/// language comes from its size information packs, or, if those weren't
+/// captured, from another block's, since every block lists the languages
+/// of all of them.
+///
+/// Text is read as ISO 8859-1. Returns `None` if there are no valid packs.
+pub fn decode(packs: &[CdTextPack]) -> Option<CdText> {
+    decode_with(packs, &CdTextOptions::default())
+}
+
+/// Decodes CD-TEXT as `decode` does, using the given options.
+pub fn decode_with(packs: &[CdTextPack], options: &CdTextOptions) -> Option<CdText> {
+    let mut blocks: Vec<CdTextBlock> = (0..8)
+        .filter_map(|block| decode_block(packs, block, options))
+        .collect();
+    if blocks.is_empty() {
+        return None;
+    }
+
+    let languages = blocks
+        .iter()
+        .find_map(|block| block.size_info)
+        .map(|info| info.languages);
+    if let Some(languages) = languages {
+        for block in blocks.iter_mut() {
+            let languages = block
+                .size_info
+                .map(|info| info.languages)
+                .unwrap_or(languages);
+            block.language = Some(languages[usize::from(block.block)]);
+        }
+    }
+
+    Some(CdText { blocks })
+}
+
+/// Decodes a single block, returning `None` if it has no usable packs.
+fn decode_block(packs: &[CdTextPack], block: u8, options: &CdTextOptions) -> Option<CdTextBlock> {
+    let mut unique: BTreeMap<u8, &CdTextPack> = BTreeMap::new();
+    for pack in packs.iter().filter(|pack| pack.block() == block) {
+        if pack.crc_valid() {
+            // A valid copy always replaces a damaged one
+            let existing = unique.entry(pack.sequence()).or_insert(pack);
+            if !existing.crc_valid() {
+                *existing = pack;
+            }
+        } else if options.ignore_crc {
+            unique.entry(pack.sequence()).or_insert(pack);
+        }
+    }
+    if unique.is_empty() {
+        return None;
+    }
+
+    let of_type = |pack_type: u8| -> Vec<&CdTextPack> {
+        unique
+            .values()
+            .filter(|pack| pack.pack_type() == pack_type)
+            .cloned()
+            .collect()
+    };
+
+    let mut cd_text = CdTextBlock {
+        block,
+        bad_crc_sequences: unique
+            .values()
+            .filter(|pack| !pack.crc_valid())
+            .map(|pack| pack.sequence())
+            .collect(),
+        ..CdTextBlock::default()
+    };
+    for pack_type in PACK_TITLE..=PACK_MESSAGE {
+        for (track, text) in split_strings(&of_type(pack_type)) {
+            if text.is_empty() {
+                continue;
+            }
+            let entry = if track == 0 {
+                &mut cd_text.album
+            } else {
+                cd_text.tracks.entry(track).or_default()
+            };
+            if let Some(field) = entry.field_mut(pack_type) {
+                *field = Some(latin1(&text));
+            }
+        }
+    }
+
+    let disc_id = stream(&of_type(PACK_DISC_ID));
+    if let Some(text) = disc_id.split(|b| *b == 0).next() {
+        if !text.is_empty() {
+            cd_text.disc_id = Some(latin1(text));
+        }
+    }
+
+    let genre = stream(&of_type(PACK_GENRE));
+    if genre.len() >= 2 {
+        let code = u16::from(genre[0]) << 8 | u16::from(genre[1]);
+        // Unwrapping is safe since split always yields at least once
+        let text = genre[2..].split(|b| *b == 0).next().unwrap();
+        cd_text.genre = Some((code, latin1(text)));
+    }
+
+    cd_text.size_info = CdTextSizeInfo::parse(&stream(&of_type(PACK_SIZE_INFO)));
+
+    Some(cd_text)
+}
+
+impl SubcodeData {
+    /// Decodes any CD-TEXT carried in this subcode's R-W channels.
+    /// See `cdtext::decode`.
+    pub fn cd_text(&self) -> Option<CdText> {
+        self.cd_text_with(&CdTextOptions::default())
+    }
+
+    /// Decodes any CD-TEXT carried in this subcode's R-W channels, using
+    /// the given options. See `cdtext::decode_with`.
+    pub fn cd_text_with(&self, options: &CdTextOptions) -> Option<CdText> {
+        let packs: Vec<CdTextPack> = self
+            .rw_packs()
+            .iter()
+            .filter_map(CdTextPack::from_rw_pack)
+            .collect();
+        decode_with(&packs, options)
+    }
+}
+
+/// Joins the text of every pack into one byte stream.
+fn stream(packs: &[&CdTextPack]) -> Vec<u8> {
+    packs.iter().flat_map(|pack| pack.text().to_vec()).collect()
+}
+
+/// Splits the text of a run of packs into its NUL-terminated strings,
+/// each paired with the track it belongs to. A string consisting only
+/// of a tab means "the same as the previous track".
+///
+/// Where a gap in the sequence numbers shows that packs are missing,
+/// such as ones dropped for failing their CRC, any string the gap cuts
+/// through is discarded rather than joined to the text after it.
+fn split_strings(packs: &[&CdTextPack]) -> Vec<(u8, Vec<u8>)> {
+    let mut strings: Vec<(u8, Vec<u8>)> = vec![];
+    let mut track = 0;
+    let mut current = vec![];
+    let mut previous_sequence: Option<u8> = None;
+
+    for pack in packs {
+        let after_gap =
+            previous_sequence.is_some_and(|previous| pack.sequence() != previous.wrapping_add(1));
+        previous_sequence = Some(pack.sequence());
+        // Text preceding this pack's first NUL finishes a string which
+        // began in a missing pack
+        let mut skipping = after_gap && pack.character_position() > 0;
+        if after_gap {
+            current.clear();
+        }
+        // The header identifies the track of the first character, so
+        // resynchronise whenever a pack begins a new string.
+        if current.is_empty() {
+            track = pack.track();
+        }
+        for byte in pack.text() {
+            if skipping {
+                if *byte == 0 {
+                    skipping = false;
+                    track = track.saturating_add(1);
+                }
+                continue;
+            }
+            if *byte != 0 {
+                current.push(*byte);
+                continue;
+            }
+
+            let text = if current == [b'\t'] {
+                strings
+                    .last()
+                    .map(|(_, previous)| previous.clone())
+                    .unwrap_or_default()
+            } else {
+                current.clone()
+            };
+            strings.push((track, text));
+            current.clear();
+            track = track.saturating_add(1);
+        }
+    }
+
+    strings
+}
+
+fn latin1(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| *byte as char).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cdtext;
+    use crate::subcode::testing;
+
+    #[test]
+    fn test_pack_header() {
+        let pack = testing::cdtext_pack([0x81, 0x83, 0x10, 0x25], *b"abcdefghijkl");
+        assert!(pack.crc_valid());
+        assert_eq!(cdtext::PACK_PERFORMER, pack.pack_type());
+        assert_eq!(3, pack.track());
+        assert_eq!(0x10, pack.sequence());
+        assert_eq!(2, pack.block());
+        assert_eq!(5, pack.character_position());
+        assert!(!pack.is_double_byte());
+        assert_eq!(b"abcdefghijkl", pack.text());
+    }
+
+    #[test]
+    fn test_titles_and_performers() {
+        let mut packs = testing::cdtext_packs(
+            cdtext::PACK_TITLE,
+            0,
+            0,
+            b"Album Title\0First Song\0Second\0",
+        );
+        let next = packs.len() as u8;
+        packs.extend(testing::cdtext_packs(
+            cdtext::PACK_PERFORMER,
+            0,
+            next,
+            b"Someone\0\t\0Someone Else\0",
+        ));
+
+        let cd_text = cdtext::decode(&packs).unwrap().blocks.remove(0);
+        assert_eq!(Some("Album Title".to_string()), cd_text.album.title);
+        assert_eq!(Some("Someone".to_string()), cd_text.album.performer);
+        assert_eq!(Some("First Song".to_string()), cd_text.tracks[&1].title);
+        assert_eq!(Some("Someone".to_string()), cd_text.tracks[&1].performer);
+        assert_eq!(Some("Second".to_string()), cd_text.tracks[&2].title);
+        assert_eq!(
+            Some("Someone Else".to_string()),
+            cd_text.tracks[&2].performer
+        );
+        assert_eq!(2, cd_text.tracks.len());
+    }
+
+    #[test]
+    fn test_dropped_pack_resynchronises_tracks() {
+        let packs = testing::cdtext_packs(
+            cdtext::PACK_TITLE,
+            0,
+            0,
+            b"Album\0Track 1\0Track 2\0Track 3\0Track 4\0",
+        );
+        assert_eq!(4, packs.len());
+        // The third pack finishes track 3's title and starts track 4's, so
+        // neither can be recovered
+        let survivors = vec![packs[0], packs[1], packs[3]];
+        let cd_text = cdtext::decode(&survivors).unwrap().blocks.remove(0);
+        assert_eq!(Some("Album".to_string()), cd_text.album.title);
+        assert_eq!(Some("Track 1".to_string()), cd_text.tracks[&1].title);
+        assert_eq!(Some("Track 2".to_string()), cd_text.tracks[&2].title);
+        assert_eq!(2, cd_text.tracks.len());
+
+        // Without the second pack, nothing is credited to the wrong track
+        let survivors = vec![packs[0], packs[2], packs[3]];
+        let cd_text = cdtext::decode(&survivors).unwrap().blocks.remove(0);
+        assert_eq!(Some("Album".to_string()), cd_text.album.title);
+        assert_eq!(Some("Track 4".to_string()), cd_text.tracks[&4].title);
+        assert_eq!(1, cd_text.tracks.len());
+    }
+
+    #[test]
+    fn test_genre_pack() {
+        let packs = testing::cdtext_packs(cdtext::PACK_GENRE, 0, 0, b"\x00\x0ESoft Rock\0");
+        let cd_text = cdtext::decode(&packs).unwrap().blocks.remove(0);
+        assert_eq!(Some((0x0E, "Soft Rock".to_string())), cd_text.genre);
+    }
+
+    #[test]
+    fn test_disc_id_and_message_packs() {
+        let mut packs = testing::cdtext_packs(cdtext::PACK_DISC_ID, 0, 0, b"XY-1234\0");
+        packs.extend(testing::cdtext_packs(
+            cdtext::PACK_MESSAGE,
+            0,
+            1,
+            b"Thanks for listening\0",
+        ));
+
+        let cd_text = cdtext::decode(&packs).unwrap().blocks.remove(0);
+        assert_eq!(Some("XY-1234".to_string()), cd_text.disc_id);
+        assert_eq!(
+            Some("Thanks for listening".to_string()),
+            cd_text.album.message
+        );
+    }
+
+    #[test]
+    fn test_bad_crc_packs_are_skipped() {
+        let mut packs = testing::cdtext_packs(cdtext::PACK_TITLE, 0, 0, b"Title\0");
+        packs[0].bytes[5] ^= 0xFF;
+        assert_eq!(None, cdtext::decode(&packs));
+    }
+
+    #[test]
+    fn test_lenient_crc_handling() {
+        let mut packs = testing::cdtext_packs(cdtext::PACK_TITLE, 0, 0, b"Album\0Track One\0Two\0");
+        packs[1].bytes[17] ^= 0xFF;
+
+        let strict = cdtext::decode(&packs).unwrap().blocks.remove(0);
+        assert_eq!(Some("Album".to_string()), strict.album.title);
+        assert_eq!(None, strict.tracks.get(&1).and_then(|t| t.title.clone()));
+        assert!(strict.bad_crc_sequences.is_empty());
+
+        let options = cdtext::CdTextOptions { ignore_crc: true };
+        let lenient = cdtext::decode_with(&packs, &options)
+            .unwrap()
+            .blocks
+            .remove(0);
+        assert_eq!(Some("Track One".to_string()), lenient.tracks[&1].title);
+        assert_eq!(Some("Two".to_string()), lenient.tracks[&2].title);
+        assert_eq!(vec![1], lenient.bad_crc_sequences);
+    }
+
+    #[test]
+    fn test_lenient_mode_prefers_valid_copies() {
+        let packs = testing::cdtext_packs(cdtext::PACK_TITLE, 0, 0, b"Title\0");
+        let mut damaged = packs[0];
+        damaged.bytes[17] ^= 0xFF;
+
+        let options = cdtext::CdTextOptions { ignore_crc: true };
+        let cd_text = cdtext::decode_with(&[damaged, packs[0]], &options)
+            .unwrap()
+            .blocks
+            .remove(0);
+        assert!(cd_text.bad_crc_sequences.is_empty());
+    }
+
+    #[test]
+    fn test_cd_text_from_subcode() {
+        let packs = testing::cdtext_packs(cdtext::PACK_TITLE, 0, 0, b"A Long Album Title\0Track\0");
+        // Repeat the packs, as a disc's lead-in does
+        let mut repeated = packs.clone();
+        repeated.extend(packs);
+        let disc = testing::disc_with_cdtext(&repeated);
+
+        let cd_text = disc.cd_text().unwrap().blocks.remove(0);
+        assert_eq!(Some("A Long Album Title".to_string()), cd_text.album.title);
+        assert_eq!(Some("Track".to_string()), cd_text.tracks[&1].title);
+    }
+
+    #[test]
+    fn test_size_info_pack() {
+        let mut info = vec![0x00, 1, 12, 0x00];
+        let mut counts = [0; 16];
+        counts[0] = 4;
+        counts[1] = 3;
+        counts[15] = 3;
+        info.extend_from_slice(&counts);
+        info.extend_from_slice(&[9, 0, 0, 0, 0, 0, 0, 0]);
+        info.extend_from_slice(&[0x09, 0, 0, 0, 0, 0, 0, 0]);
+        let packs = testing::cdtext_packs(cdtext::PACK_SIZE_INFO, 0, 7, &info);
+        assert_eq!(3, packs.len());
+
+        let size_info = cdtext::decode(&packs)
+            .unwrap()
+            .blocks
+            .remove(0)
+            .size_info
+            .unwrap();
+        assert_eq!(0x00, size_info.character_code);
+        assert_eq!(1, size_info.first_track);
+        assert_eq!(12, size_info.last_track);
+        assert_eq!(4, size_info.pack_count(cdtext::PACK_TITLE));
+        assert_eq!(3, size_info.pack_count(cdtext::PACK_PERFORMER));
+        assert_eq!(3, size_info.pack_count(cdtext::PACK_SIZE_INFO));
+        assert_eq!(0, size_info.pack_count(0x40));
+        assert_eq!(9, size_info.last_sequences[0]);
+        // English
+        assert_eq!(0x09, size_info.languages[0]);
+    }
+
+    #[test]
+    fn test_multiple_language_blocks() {
+        let mut packs = testing::cdtext_packs(cdtext::PACK_TITLE, 0, 0, b"Album\0Song\0");
+        let mut info = vec![0x00, 1, 1, 0x00];
+        info.extend_from_slice(&[0; 24]);
+        info.extend_from_slice(&[0x09, 0x69, 0, 0, 0, 0, 0, 0]);
+        packs.extend(testing::cdtext_packs(cdtext::PACK_SIZE_INFO, 0, 1, &info));
+        // The second block's size information wasn't captured
+        packs.extend(testing::cdtext_packs(
+            cdtext::PACK_TITLE,
+            1,
+            0,
+            b"Arubamu\0Uta\0",
+        ));
+
+        let cd_text = cdtext::decode(&packs).unwrap();
+        assert_eq!(2, cd_text.blocks.len());
+
+        let english = cd_text.language(0x09).unwrap();
+        assert_eq!(0, english.block);
+        assert_eq!(Some("Album".to_string()), english.album.title);
+        assert_eq!(Some("Song".to_string()), english.tracks[&1].title);
+
+        let japanese = cd_text.language(0x69).unwrap();
+        assert_eq!(1, japanese.block);
+        assert_eq!(Some("Arubamu".to_string()), japanese.album.title);
+        assert_eq!(Some("Uta".to_string()), japanese.tracks[&1].title);
+        assert_eq!(None, japanese.size_info);
+    }
+}
@@ -0,0 +1,29 @@
+/// Computes the CRC-16/CCITT checksum (polynomial 0x1021, initial value 0)
+/// used by both the Q channel and CD-TEXT packs. Both store the result
+/// inverted.
+pub(crate) fn crc16(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0;
+    for byte in data {
+        crc ^= u16::from(*byte) << 8;
+        for _ in 0..8 {
+            if crc & 0x8000 != 0 {
+                crc = (crc << 1) ^ 0x1021;
+            } else {
+                crc <<= 1;
+            }
+        }
+    }
+
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::crc;
+
+    #[test]
+    fn test_crc16() {
+        // Standard check value for CRC-16/XMODEM
+        assert_eq!(0x31C3, crc::crc16(b"123456789"));
+    }
+}
@@ -0,0 +1,83 @@
+//! A single error type covering everything the crate can fail with.
+
+use std::io;
+
+#[cfg(feature = "bincode")]
+use crate::report::ReportFormatError;
+#[cfg(feature = "rw_ecc")]
+use crate::rw::EccError;
+use crate::subcode::InvalidDataError;
+
+/// Wraps each of the crate's more specific errors, so that callers using
+/// several parts of the crate can propagate all of them with `?`.
+#[derive(Debug, Fail)]
+pub enum CcdError {
+    #[fail(display = "{}", _0)]
+    InvalidData(#[cause] InvalidDataError),
+
+    #[fail(display = "{}", _0)]
+    Io(#[cause] io::Error),
+
+    #[cfg(feature = "rw_ecc")]
+    #[fail(display = "{}", _0)]
+    Ecc(#[cause] EccError),
+
+    #[cfg(feature = "bincode")]
+    #[fail(display = "{}", _0)]
+    ReportFormat(#[cause] ReportFormatError),
+}
+
+impl From<InvalidDataError> for CcdError {
+    fn from(error: InvalidDataError) -> CcdError {
+        CcdError::InvalidData(error)
+    }
+}
+
+impl From<io::Error> for CcdError {
+    fn from(error: io::Error) -> CcdError {
+        CcdError::Io(error)
+    }
+}
+
+#[cfg(feature = "rw_ecc")]
+impl From<EccError> for CcdError {
+    fn from(error: EccError) -> CcdError {
+        CcdError::Ecc(error)
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl From<ReportFormatError> for CcdError {
+    fn from(error: ReportFormatError) -> CcdError {
+        CcdError::ReportFormat(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::CcdError;
+    use crate::io;
+    use crate::subcode::SubcodeData;
+    use std::io::Cursor;
+
+    fn parse(data: Vec<u8>) -> Result<SubcodeData, CcdError> {
+        Ok(SubcodeData::parse(data)?)
+    }
+
+    fn read_sbi(data: Vec<u8>) -> Result<usize, CcdError> {
+        Ok(io::read_sbi(Cursor::new(data))?.len())
+    }
+
+    #[test]
+    fn test_errors_convert_into_ccd_error() {
+        match parse(vec![0; 95]) {
+            Err(CcdError::InvalidData(_)) => (),
+            other => panic!("unexpected result {:?}", other),
+        }
+        match read_sbi(b"SUB\0".to_vec()) {
+            Err(CcdError::Io(_)) => (),
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert!(parse(vec![0; 96]).is_ok());
+    }
+}
@@ -0,0 +1,380 @@
+//! Loading subcode from the file formats used by dumping tools.
+
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::crc;
+use crate::error::CcdError;
+use crate::msf::Msf;
+use crate::report::DiscReport;
+use crate::subcode::layout::read_full;
+use crate::subcode::{ChannelSet, Sector, SubcodeData, SubcodeType};
+
+const SBI_MAGIC: &[u8; 4] = b"SBI\0";
+
+/// Loads and analyzes every `.sub` file in `dir`, returning each file's
+/// path along with its report, or the error that stopped it from being
+/// read. A file which fails doesn't stop the rest from being processed.
+/// Files are matched by extension, ignoring case, and subdirectories
+/// aren't searched. Results are sorted by path.
+///
+/// If the directory itself can't be listed, the result is a single
+/// entry pairing `dir` with the `CcdError::Io` error.
+pub fn analyze_directory<P: AsRef<Path>>(dir: P) -> Vec<(PathBuf, Result<DiscReport, CcdError>)> {
+    let dir = dir.as_ref();
+    let paths = match sub_files(dir) {
+        Ok(paths) => paths,
+        Err(error) => return vec![(dir.to_path_buf(), Err(CcdError::from(error)))],
+    };
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let report = fs::read(&path)
+                .map_err(CcdError::from)
+                .and_then(|data| Ok(SubcodeData::parse(data)?.analyze()));
+            (path, report)
+        })
+        .collect()
+}
+
+/// Lists the `.sub` files directly inside `dir`, sorted by path.
+fn sub_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut paths = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_sub = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("sub"));
+        if is_sub && path.is_file() {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    Ok(paths)
+}
+
+/// Loads an `.sbi` (subchannel information) file, which records the Q
+/// channel of only those sectors a copy protection scheme has altered.
+/// Returns each sector keyed by its LBA. See `read_sbi`.
+pub fn load_sbi<P: AsRef<Path>>(path: P) -> io::Result<Vec<(i32, Sector)>> {
+    read_sbi(BufReader::new(File::open(path)?))
+}
+
+/// Reads `.sbi` data. The format is a 4-byte `SBI\0` header followed by
+/// entries made of a BCD absolute MSF, a type byte, and for type 1 the
+/// first 10 bytes of the Q frame.
+///
+/// The file doesn't store the Q CRC. Since the sectors it lists are the
+/// ones whose Q channel was deliberately damaged, each sector is given
+/// a CRC which fails to validate, as the original disc would. Other
+/// channels are left empty. Entries of any other type, or with an
+/// invalid MSF, are reported as `InvalidData` errors.
+pub fn read_sbi<R: Read>(mut reader: R) -> io::Result<Vec<(i32, Sector)>> {
+    let mut magic = [0; 4];
+    if read_full(&mut reader, &mut magic)? != magic.len() || &magic != SBI_MAGIC {
+        return Err(invalid_data("missing SBI header".to_string()));
+    }
+
+    let mut entries = vec![];
+    let mut header = [0; 4];
+    loop {
+        match read_full(&mut reader, &mut header)? {
+            0 => break,
+            4 => (),
+            n => {
+                return Err(invalid_data(format!(
+                    "incomplete SBI entry; header was {} bytes",
+                    n
+                )))
+            }
+        }
+
+        let msf = match Msf::from_bcd(header[0], header[1], header[2]) {
+            Some(msf) => msf,
+            None => {
+                return Err(invalid_data(format!(
+                    "invalid SBI entry time {:02x}:{:02x}:{:02x}",
+                    header[0], header[1], header[2]
+                )))
+            }
+        };
+        if header[3] != 1 {
+            return Err(invalid_data(format!(
+                "unsupported SBI entry type {} at {}",
+                header[3], msf
+            )));
+        }
+
+        let mut q = [0; 12];
+        if read_full(&mut reader, &mut q[..10])? != 10 {
+            return Err(invalid_data(format!("incomplete SBI entry at {}", msf)));
+        }
+        // Store the CRC un-inverted, so that it never matches
+        let crc = crc::crc16(&q[..10]);
+        q[10] = (crc >> 8) as u8;
+        q[11] = crc as u8;
+
+        let mut data = vec![0; 12];
+        data.extend_from_slice(&q);
+        data.extend_from_slice(&[0; 72]);
+        // We unwrap here because the data is always exactly 96 bytes
+        entries.push((msf.to_lba(), Sector::parse(data).unwrap()));
+    }
+
+    Ok(entries)
+}
+
+impl SubcodeData {
+    /// Replaces the Q channel of each sector listed in an SBI file, as
+    /// returned by `read_sbi`, with the SBI's copy. This restores the
+    /// deliberately corrupted Q frames of a protected disc to a dump that
+    /// was made without them. Only the Q channel is replaced, since that's
+    /// all an SBI file records.
+    ///
+    /// Sectors are matched by LBA, counting from the first CRC-valid
+    /// absolute time in the dump rather than decoding each sector's own
+    /// time, which is exactly what's damaged on the sectors being patched.
+    /// Entries outside the dump are skipped. Returns the number of sectors
+    /// replaced.
+    pub fn apply_sbi(&mut self, entries: &[(i32, Sector)]) -> usize {
+        let base = self
+            .channel_iter(SubcodeType::Q)
+            .enumerate()
+            .filter(|(_, q)| q.q_crc_valid())
+            .find_map(|(i, q)| q.q_absolute().map(|absolute| absolute.to_lba() - i as i32));
+        let base = match base {
+            Some(base) => base,
+            None => return 0,
+        };
+
+        let mut applied = 0;
+        for (lba, sector) in entries {
+            let index = lba - base;
+            if index < 0 || index as usize >= self.sectors.len() {
+                continue;
+            }
+            *self.sectors[index as usize].channel_mut(SubcodeType::Q) =
+                sector.channel(SubcodeType::Q).clone();
+            applied += 1;
+        }
+
+        applied
+    }
+
+    /// Writes each channel to its own file in `dir`, named `p.bin`
+    /// through `w.bin`. Each file holds that channel's 12 bytes from every
+    /// sector, in order. The directory must already exist, and any
+    /// existing files are overwritten.
+    pub fn write_channels_split<P: AsRef<Path>>(&self, dir: P) -> io::Result<()> {
+        for channel in ChannelSet::all().iter() {
+            let name = format!("{}.bin", channel.to_string().to_lowercase());
+            let mut out = BufWriter::new(File::create(dir.as_ref().join(name))?);
+            for code in self.channel_iter(channel) {
+                out.write_all(&code.data)?;
+            }
+            out.flush()?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl SubcodeData {
+    /// Parses a `.sub` file by memory-mapping it rather than reading it
+    /// into memory first, which avoids a large allocation for a whole
+    /// disc's subcode. The file must be a multiple of 96 bytes, as with
+    /// `SubcodeData::parse`; otherwise an `InvalidData` error is returned.
+    ///
+    /// The file shouldn't be modified while it's being parsed.
+    pub fn from_mmap(path: &Path) -> io::Result<SubcodeData> {
+        let file = File::open(path)?;
+        // Safety: the map is only read during this call, and the caller
+        // is responsible for not truncating the file while it's mapped.
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        if map.len() % 96 != 0 {
+            return Err(invalid_data(format!(
+                "subcode data must be a multiple of 96 bytes, was {}",
+                map.len()
+            )));
+        }
+
+        let sectors = map
+            .chunks(96)
+            .map(|chunk| Sector::parse(chunk.to_vec()))
+            .collect::<Result<Vec<Sector>, _>>()
+            .map_err(|e| invalid_data(e.to_string()))?;
+
+        Ok(SubcodeData { sectors })
+    }
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::io;
+    use crate::msf::Msf;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_load_sbi_fixture() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/libcrypt.sbi");
+        let entries = io::load_sbi(path).unwrap();
+        assert_eq!(2, entries.len());
+
+        let (lba, ref sector) = entries[0];
+        assert_eq!(Msf::new(3, 8, 5).to_lba(), lba);
+        let q = sector.codes[1].clone();
+        assert_eq!(Some(1), q.q_track());
+        assert_eq!(Some(Msf::new(3, 6, 5)), q.q_relative());
+        assert!(!q.q_crc_valid());
+        assert!(sector.codes[0].is_empty());
+
+        assert_eq!(Msf::new(3, 8, 20).to_lba(), entries[1].0);
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_from_mmap() {
+        use crate::subcode::SubcodeData;
+
+        let path = std::env::temp_dir().join(format!("ccd-mmap-{}.sub", std::process::id()));
+        let data: Vec<u8> = (0..96 * 4).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&path, &data).unwrap();
+
+        let result = SubcodeData::from_mmap(&path);
+        std::fs::write(&path, &data[..100]).unwrap();
+        let truncated = SubcodeData::from_mmap(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let subcode_data = result.unwrap();
+        assert_eq!(4, subcode_data.sectors.len());
+        assert_eq!(data, subcode_data.to_bytes());
+        assert!(truncated.is_err());
+    }
+
+    #[test]
+    fn test_write_channels_split() {
+        use crate::subcode::SubcodeData;
+
+        let dir = std::env::temp_dir().join(format!("ccd-split-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let data: Vec<u8> = (0..96 * 3).map(|i| (i / 12) as u8).collect();
+        let result = SubcodeData::parse(data).unwrap().write_channels_split(&dir);
+
+        let mut files = vec![];
+        for name in &["p", "q", "r", "s", "t", "u", "v", "w"] {
+            files.push(std::fs::read(dir.join(format!("{}.bin", name))).unwrap());
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        result.unwrap();
+        for (channel, file) in files.iter().enumerate() {
+            assert_eq!(12 * 3, file.len());
+            // The second sector's copy of this channel
+            assert_eq!(8 + channel as u8, file[12]);
+        }
+    }
+
+    #[test]
+    fn test_analyze_directory() {
+        use crate::error::CcdError;
+        use crate::subcode::{DiscBuilder, TrackType};
+
+        let dir = std::env::temp_dir().join(format!("ccd-batch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let disc = DiscBuilder::new()
+            .add_track(1, Msf::new(0, 2, 0), 10, TrackType::Audio)
+            .build();
+        std::fs::write(dir.join("a.sub"), disc.to_bytes()).unwrap();
+        std::fs::write(dir.join("b.SUB"), vec![0; 100]).unwrap();
+        std::fs::write(dir.join("c.img"), vec![0; 96]).unwrap();
+        let results = io::analyze_directory(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(2, results.len());
+        assert_eq!(dir.join("a.sub"), results[0].0);
+        assert_eq!(disc.analyze(), *results[0].1.as_ref().unwrap());
+        assert_eq!(dir.join("b.SUB"), results[1].0);
+        match results[1].1 {
+            Err(CcdError::InvalidData(_)) => (),
+            ref other => panic!("unexpected result {:?}", other),
+        }
+
+        let missing = io::analyze_directory(dir.join("missing"));
+        assert_eq!(1, missing.len());
+        assert_eq!(dir.join("missing"), missing[0].0);
+        match missing[0].1 {
+            Err(CcdError::Io(_)) => (),
+            ref other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_apply_sbi() {
+        use crate::subcode::{DiscBuilder, TrackType};
+
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/libcrypt.sbi");
+        let entries = io::load_sbi(path).unwrap();
+        // The first entry is at 03:08:05, 8 sectors into this disc
+        let mut disc = DiscBuilder::new()
+            .add_track(1, Msf::new(3, 7, 72), 30, TrackType::Audio)
+            .build();
+        let original = disc.clone();
+
+        assert_eq!(2, disc.apply_sbi(&entries));
+        assert_ne!(original.sectors[8].to_bytes(), disc.sectors[8].to_bytes());
+        assert_eq!(entries[0].1.codes[1].data, disc.sectors[8].codes[1].data);
+        assert_eq!(
+            original.sectors[8].codes[0].data,
+            disc.sectors[8].codes[0].data
+        );
+        assert_eq!(original.sectors[7].to_bytes(), disc.sectors[7].to_bytes());
+    }
+
+    #[test]
+    fn test_apply_sbi_with_corrupt_leading_frames() {
+        use crate::subcode::{DiscBuilder, TrackType};
+
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/libcrypt.sbi");
+        let entries = io::load_sbi(path).unwrap();
+        let mut disc = DiscBuilder::new()
+            .add_track(1, Msf::new(3, 7, 72), 30, TrackType::Audio)
+            .build();
+        // The first three sectors claim to be a minute later, but fail
+        // their CRC checks, so the dump is placed by sector 3 instead
+        for sector in &mut disc.sectors[..3] {
+            sector.codes[1].data[7] = 0x04;
+        }
+        disc.sectors[8].codes[0].data = vec![0xFF; 12];
+        disc.sectors[8].codes[7].data = vec![0x3C; 12];
+        let original = disc.clone();
+
+        assert_eq!(2, disc.apply_sbi(&entries));
+        assert_eq!(entries[0].1.codes[1].data, disc.sectors[8].codes[1].data);
+        // Only Q is replaced, so P and R-W survive
+        assert_eq!(vec![0xFF; 12], disc.sectors[8].codes[0].data);
+        assert_eq!(vec![0x3C; 12], disc.sectors[8].codes[7].data);
+        for i in 0..8 {
+            assert_eq!(original.sectors[i].to_bytes(), disc.sectors[i].to_bytes());
+        }
+    }
+
+    #[test]
+    fn test_read_sbi_rejects_bad_header() {
+        assert!(io::read_sbi(Cursor::new(b"SUB\0".to_vec())).is_err());
+    }
+
+    #[test]
+    fn test_read_sbi_rejects_truncated_entry() {
+        let mut data = b"SBI\0".to_vec();
+        data.extend_from_slice(&[0x03, 0x08, 0x05, 0x01, 0x41, 0x01]);
+        assert!(io::read_sbi(Cursor::new(data)).is_err());
+    }
+}
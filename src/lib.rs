@@ -1,4 +1,29 @@
+// failure_derive's impls trip this lint on current compilers.
+#![allow(non_local_definitions)]
+
 #[macro_use]
 extern crate failure_derive;
 
+/// Reports a decoding problem through the `log` crate, under the `ccd`
+/// target. Compiles to nothing unless the `decode_warnings` feature is
+/// enabled.
+macro_rules! warn {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "decode_warnings")]
+        log::warn!(target: "ccd", $($arg)*);
+    };
+}
+
+pub mod bcd;
+pub mod cdmidi;
+pub mod cdtext;
+mod crc;
+pub mod error;
+pub mod io;
+pub mod msf;
+pub mod report;
+pub mod rw;
 pub mod subcode;
+pub mod toc;
+
+pub use crate::error::CcdError;
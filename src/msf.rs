@@ -0,0 +1,149 @@
+//! Minute/second/frame timestamps, the unit of time used throughout
+//! the Q channel.
+
+use std::fmt;
+
+use crate::bcd;
+
+/// Number of frames (sectors) in one second of CD audio.
+pub const FRAMES_PER_SECOND: u32 = 75;
+
+/// Number of frames in one minute of CD audio.
+pub const FRAMES_PER_MINUTE: u32 = FRAMES_PER_SECOND * 60;
+
+/// The number of frames preceding LBA 0; the first track's
+/// index 01 conventionally begins at 00:02:00.
+pub const LBA_OFFSET: i32 = 150;
+
+/// A timestamp expressed in minutes, seconds and frames, where each
+/// frame is 1/75th of a second and corresponds to one sector.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "bincode", derive(serde::Serialize, serde::Deserialize))]
+pub struct Msf {
+    pub minute: u8,
+    pub second: u8,
+    pub frame: u8,
+}
+
+impl Msf {
+    pub fn new(minute: u8, second: u8, frame: u8) -> Msf {
+        Msf {
+            minute,
+            second,
+            frame,
+        }
+    }
+
+    /// Builds an `Msf` from a total number of frames. Frame counts
+    /// beyond 255 minutes wrap, as they can't be represented.
+    pub fn from_frames(frames: u32) -> Msf {
+        Msf {
+            minute: (frames / FRAMES_PER_MINUTE) as u8,
+            second: ((frames % FRAMES_PER_MINUTE) / FRAMES_PER_SECOND) as u8,
+            frame: (frames % FRAMES_PER_SECOND) as u8,
+        }
+    }
+
+    /// Decodes an `Msf` from the three BCD bytes used to store times in the
+    /// Q channel. Returns `None` if any byte isn't valid BCD, or if the
+    /// seconds or frames are out of range.
+    pub fn from_bcd(minute: u8, second: u8, frame: u8) -> Option<Msf> {
+        let msf = Msf {
+            minute: bcd::from_bcd(minute)?,
+            second: bcd::from_bcd(second)?,
+            frame: bcd::from_bcd(frame)?,
+        };
+        if u32::from(msf.second) >= 60 || u32::from(msf.frame) >= FRAMES_PER_SECOND {
+            return None;
+        }
+
+        Some(msf)
+    }
+
+    /// Like `from_bcd`, but produces an approximate time instead of
+    /// failing, along with whether the input was entirely valid. Invalid
+    /// nibbles are decoded as described in `bcd::from_bcd_lenient`, and
+    /// the result is normalised so that out-of-range seconds or frames
+    /// carry into the next unit.
+    pub fn from_bcd_lenient(minute: u8, second: u8, frame: u8) -> (Msf, bool) {
+        if let Some(msf) = Msf::from_bcd(minute, second, frame) {
+            return (msf, true);
+        }
+
+        let (minute, _) = bcd::from_bcd_lenient(minute);
+        let (second, _) = bcd::from_bcd_lenient(second);
+        let (frame, _) = bcd::from_bcd_lenient(frame);
+        let frames = u32::from(minute) * FRAMES_PER_MINUTE
+            + u32::from(second) * FRAMES_PER_SECOND
+            + u32::from(frame);
+        (Msf::from_frames(frames), false)
+    }
+
+    /// Returns the total number of frames this timestamp represents.
+    pub fn frames(&self) -> u32 {
+        u32::from(self.minute) * FRAMES_PER_MINUTE
+            + u32::from(self.second) * FRAMES_PER_SECOND
+            + u32::from(self.frame)
+    }
+
+    /// Converts an absolute disc time into a logical block address,
+    /// where 00:02:00 is LBA 0.
+    pub fn to_lba(&self) -> i32 {
+        self.frames() as i32 - LBA_OFFSET
+    }
+}
+
+impl fmt::Display for Msf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:02}:{:02}:{:02}", self.minute, self.second, self.frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::msf::Msf;
+
+    #[test]
+    fn test_frames_round_trip() {
+        let msf = Msf::new(12, 34, 56);
+        assert_eq!(12 * 4500 + 34 * 75 + 56, msf.frames());
+        assert_eq!(msf, Msf::from_frames(msf.frames()));
+    }
+
+    #[test]
+    fn test_from_bcd() {
+        assert_eq!(Some(Msf::new(1, 23, 45)), Msf::from_bcd(0x01, 0x23, 0x45));
+        assert_eq!(None, Msf::from_bcd(0x01, 0x60, 0x00));
+        assert_eq!(None, Msf::from_bcd(0x01, 0x00, 0x75));
+        assert_eq!(None, Msf::from_bcd(0x0A, 0x00, 0x00));
+    }
+
+    #[test]
+    fn test_to_lba() {
+        assert_eq!(0, Msf::new(0, 2, 0).to_lba());
+        assert_eq!(-150, Msf::new(0, 0, 0).to_lba());
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!("01:02:03", Msf::new(1, 2, 3).to_string());
+    }
+
+    #[test]
+    fn test_from_bcd_lenient() {
+        assert_eq!(
+            (Msf::new(1, 2, 3), true),
+            Msf::from_bcd_lenient(0x01, 0x02, 0x03)
+        );
+        // 0x1A frames decodes to 20
+        assert_eq!(
+            (Msf::new(1, 2, 20), false),
+            Msf::from_bcd_lenient(0x01, 0x02, 0x1A)
+        );
+        // 0x6A seconds decodes to 70, which carries into the minute
+        assert_eq!(
+            (Msf::new(2, 10, 0), false),
+            Msf::from_bcd_lenient(0x01, 0x6A, 0x00)
+        );
+    }
+}
@@ -0,0 +1,205 @@
+//! A summary of a disc's subcode, gathered in one place so that it can be
+//! cached or compared without keeping the subcode itself around.
+
+use crate::subcode::channels::bit_density;
+use crate::subcode::{ChannelSet, SubcodeData, SubcodeType, Track};
+use crate::toc::Toc;
+
+/// Version tag written at the start of `DiscReport::to_bytes`. It's
+/// increased whenever the encoding changes, so that reports written by
+/// an older version are rejected rather than misread.
+#[cfg(feature = "bincode")]
+pub const REPORT_FORMAT_VERSION: u8 = 1;
+
+/// The results of `SubcodeData::analyze`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "bincode", derive(serde::Serialize, serde::Deserialize))]
+pub struct DiscReport {
+    /// Number of sectors in the dump.
+    pub sectors: usize,
+    /// Number of sectors whose Q frame failed its CRC.
+    pub crc_failures: usize,
+    pub catalog: Option<String>,
+    /// Every track found in the program area, along with its ISRC.
+    pub tracks: Vec<Track>,
+    /// The TOC from the lead-in, which is empty if the dump doesn't
+    /// include the lead-in.
+    pub toc: Toc,
+    /// The summary of each sector produced by `to_flag_stream`.
+    pub flags: Vec<u8>,
+}
+
+impl SubcodeData {
+    /// Gathers the disc's layout and the overall health of its subcode
+    /// into a `DiscReport`.
+    pub fn analyze(&self) -> DiscReport {
+        DiscReport {
+            sectors: self.sectors.len(),
+            crc_failures: self
+                .channel_iter(SubcodeType::Q)
+                .filter(|q| !q.q_crc_valid())
+                .count(),
+            catalog: self.catalog(),
+            tracks: self.tracks(),
+            toc: self.parse_toc(),
+            flags: self.to_flag_stream(),
+        }
+    }
+}
+
+/// Statistics gathered by `SubcodeData::streaming_stats`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FullStats {
+    /// Number of sectors in the dump.
+    pub sectors: usize,
+    /// Number of sectors whose Q frame failed its CRC.
+    pub crc_failures: usize,
+    /// The same as `SubcodeData::populated_channels`.
+    pub populated_channels: ChannelSet,
+    /// The same as `SubcodeData::channel_bit_density`.
+    pub channel_bit_density: [f64; 8],
+}
+
+impl SubcodeData {
+    /// Computes the sector and CRC failure counts of `analyze` along with
+    /// `populated_channels` and `channel_bit_density`, visiting each
+    /// sector only once rather than once per statistic.
+    pub fn streaming_stats(&self) -> FullStats {
+        let mut crc_failures = 0;
+        let mut populated_channels = ChannelSet::new();
+        let mut counts = [0; 8];
+
+        for sector in &self.sectors {
+            for (i, code) in sector.codes.iter().enumerate() {
+                let set: usize = code
+                    .data
+                    .iter()
+                    .map(|byte| byte.count_ones() as usize)
+                    .sum();
+                if set > 0 {
+                    populated_channels.insert(code.channel);
+                }
+                counts[i] += set;
+            }
+            if !sector.channel(SubcodeType::Q).q_crc_valid() {
+                crc_failures += 1;
+            }
+        }
+
+        FullStats {
+            sectors: self.sectors.len(),
+            crc_failures,
+            populated_channels,
+            channel_bit_density: bit_density(counts, self.sectors.len()),
+        }
+    }
+}
+
+/// An error reading a report written by `DiscReport::to_bytes`.
+#[cfg(feature = "bincode")]
+#[derive(Debug, Fail)]
+pub enum ReportFormatError {
+    #[fail(display = "unsupported report format version {}", version)]
+    UnsupportedVersion { version: u8 },
+
+    #[fail(display = "malformed report: {}", message)]
+    Malformed { message: String },
+}
+
+#[cfg(feature = "bincode")]
+impl DiscReport {
+    /// Encodes the report compactly with bincode. The first byte is
+    /// always `REPORT_FORMAT_VERSION`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![REPORT_FORMAT_VERSION];
+        // We unwrap here because every field can be represented by
+        // bincode, and writing to a Vec can't fail
+        bytes.extend(bincode::serialize(self).unwrap());
+        bytes
+    }
+
+    /// Decodes a report written by `to_bytes`. Reports written with a
+    /// different format version are rejected.
+    pub fn from_bytes(bytes: &[u8]) -> Result<DiscReport, ReportFormatError> {
+        match bytes.first() {
+            Some(&REPORT_FORMAT_VERSION) => (),
+            Some(&version) => return Err(ReportFormatError::UnsupportedVersion { version }),
+            None => {
+                return Err(ReportFormatError::Malformed {
+                    message: "report is empty".to_string(),
+                })
+            }
+        }
+
+        bincode::deserialize(&bytes[1..]).map_err(|e| ReportFormatError::Malformed {
+            message: e.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::msf::Msf;
+    use crate::subcode::{DiscBuilder, TrackType};
+
+    fn disc() -> crate::subcode::SubcodeData {
+        DiscBuilder::new()
+            .start(Msf::new(0, 0, 0))
+            .add_track(1, Msf::new(0, 2, 0), 200, TrackType::Audio)
+            .add_track(2, Msf::new(0, 5, 0), 100, TrackType::Data)
+            .catalog("0123456789012")
+            .isrc(1, "USABC9900001")
+            .lead_out(5)
+            .build()
+    }
+
+    #[test]
+    fn test_analyze() {
+        let mut disc = disc();
+        disc.sectors[10].codes[1].data[10] ^= 0xFF;
+
+        let report = disc.analyze();
+        assert_eq!(480, report.sectors);
+        assert_eq!(1, report.crc_failures);
+        assert_eq!(Some("0123456789012".to_string()), report.catalog);
+        assert_eq!(2, report.tracks.len());
+        assert_eq!(Some("USABC9900001".to_string()), report.tracks[0].isrc);
+        assert!(report.toc.entries.is_empty());
+        assert_eq!(480, report.flags.len());
+    }
+
+    #[test]
+    fn test_streaming_stats() {
+        let mut disc = disc();
+        disc.sectors[10].codes[1].data[10] ^= 0xFF;
+        disc.sectors[20].codes[5].data[0] = 0xFF;
+
+        let stats = disc.streaming_stats();
+        let report = disc.analyze();
+        assert_eq!(report.sectors, stats.sectors);
+        assert_eq!(report.crc_failures, stats.crc_failures);
+        assert_eq!(disc.populated_channels(), stats.populated_channels);
+        assert_eq!(disc.channel_bit_density(), stats.channel_bit_density);
+    }
+
+    #[cfg(feature = "bincode")]
+    #[test]
+    fn test_report_bytes_round_trip() {
+        use crate::report::{DiscReport, ReportFormatError, REPORT_FORMAT_VERSION};
+
+        let report = disc().analyze();
+        let bytes = report.to_bytes();
+        assert_eq!(REPORT_FORMAT_VERSION, bytes[0]);
+        assert_eq!(report, DiscReport::from_bytes(&bytes).unwrap());
+
+        let mut future = bytes.clone();
+        future[0] = REPORT_FORMAT_VERSION + 1;
+        match DiscReport::from_bytes(&future) {
+            Err(ReportFormatError::UnsupportedVersion { version }) => {
+                assert_eq!(REPORT_FORMAT_VERSION + 1, version)
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert!(DiscReport::from_bytes(&bytes[..bytes.len() / 2]).is_err());
+    }
+}
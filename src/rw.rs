@@ -0,0 +1,267 @@
+//! Access to the R through W channels as a stream of 6-bit symbols
+//! grouped into packs, the form used by CD+G, CD-MIDI and CD-TEXT.
+
+use crate::cdmidi::{MIDI_ITEM, MIDI_MODE};
+use crate::cdtext::CdTextPack;
+use crate::subcode::{Sector, SubcodeData};
+
+#[cfg(feature = "rw_ecc")]
+mod ecc;
+#[cfg(feature = "rw_ecc")]
+pub use self::ecc::EccError;
+
+/// Number of symbols in a pack.
+pub const PACK_SYMBOLS: usize = 24;
+
+/// Number of packs carried by each sector.
+pub const PACKS_PER_SECTOR: usize = 4;
+
+/// Mode and item of CD+G (TV graphics) packs.
+pub(crate) const GRAPHICS_MODE: u8 = 1;
+pub(crate) const GRAPHICS_ITEM: u8 = 1;
+
+/// The fraction of packs which must look intact for `rw_health` to
+/// report `RwHealth::Good`.
+const GOOD_PACK_RATIO: f64 = 0.95;
+
+/// An estimate of how intact a disc's R-W data is. See
+/// `SubcodeData::rw_health`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RwHealth {
+    /// Nearly every pack looks intact.
+    Good,
+    /// Only the given fraction of packs, from 0.0 to 1.0, look intact.
+    Degraded(f64),
+    /// The R-W channels are empty.
+    Absent,
+}
+
+/// A single R-W pack of 24 6-bit symbols. Each symbol holds one bit from
+/// each of the R through W channels, with R in the most significant bit.
+///
+/// Symbols are stored in the order they appear in the subcode; the
+/// interleaving applied to R-W data on the physical disc isn't undone.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RwPack {
+    pub symbols: [u8; PACK_SYMBOLS],
+}
+
+impl RwPack {
+    /// Returns the 3-bit mode from the pack's first symbol.
+    pub fn mode(&self) -> u8 {
+        (self.symbols[0] >> 3) & 0b111
+    }
+
+    /// Returns the 3-bit item from the pack's first symbol, which refines
+    /// the mode.
+    pub fn item(&self) -> u8 {
+        self.symbols[0] & 0b111
+    }
+
+    /// Returns the instruction symbol.
+    pub fn instruction(&self) -> u8 {
+        self.symbols[1] & 0x3F
+    }
+
+    /// Returns the 16 data symbols, which sit between the Q parity
+    /// symbols and the P parity symbols.
+    pub fn data(&self) -> &[u8] {
+        &self.symbols[4..20]
+    }
+
+    /// Packs the 16 data symbols into 12 bytes, most significant bit first.
+    pub fn data_bytes(&self) -> [u8; 12] {
+        let mut bytes = [0; 12];
+        symbols_to_bytes(self.data(), &mut bytes);
+        bytes
+    }
+
+    /// Packs all 24 symbols into 18 bytes, most significant bit first.
+    /// This is how formats such as CD-TEXT view each pack.
+    pub fn to_bytes(&self) -> [u8; 18] {
+        let mut bytes = [0; 18];
+        symbols_to_bytes(&self.symbols, &mut bytes);
+        bytes
+    }
+
+    /// Builds a pack from 18 bytes, the inverse of `to_bytes`.
+    pub fn from_bytes(bytes: &[u8; 18]) -> RwPack {
+        let mut symbols = [0; PACK_SYMBOLS];
+        for bit in 0..PACK_SYMBOLS * 6 {
+            if bytes[bit / 8] & (0x80 >> (bit % 8)) != 0 {
+                symbols[bit / 6] |= 0x20 >> (bit % 6);
+            }
+        }
+
+        RwPack { symbols }
+    }
+}
+
+/// Concatenates 6-bit symbols into `bytes`, most significant bit first.
+fn symbols_to_bytes(symbols: &[u8], bytes: &mut [u8]) {
+    for (i, symbol) in symbols.iter().enumerate() {
+        for bit in 0..6 {
+            if symbol & (0x20 >> bit) != 0 {
+                let position = i * 6 + bit;
+                bytes[position / 8] |= 0x80 >> (position % 8);
+            }
+        }
+    }
+}
+
+impl Sector {
+    /// Reassembles the R through W channels into this sector's four packs.
+    pub fn rw_packs(&self) -> [RwPack; PACKS_PER_SECTOR] {
+        let mut packs = [RwPack {
+            symbols: [0; PACK_SYMBOLS],
+        }; PACKS_PER_SECTOR];
+
+        for (channel, code) in self.codes[2..].iter().enumerate() {
+            for frame in 0..PACKS_PER_SECTOR * PACK_SYMBOLS {
+                if code.data[frame / 8] & (0x80 >> (frame % 8)) != 0 {
+                    packs[frame / PACK_SYMBOLS].symbols[frame % PACK_SYMBOLS] |= 0x20 >> channel;
+                }
+            }
+        }
+
+        packs
+    }
+}
+
+impl SubcodeData {
+    /// Returns every R-W pack on the disc, four per sector, in order.
+    pub fn rw_packs(&self) -> Vec<RwPack> {
+        self.sectors
+            .iter()
+            .flat_map(|sector| sector.rw_packs().to_vec())
+            .collect()
+    }
+
+    /// Estimates whether the disc's R-W data is recoverable. Every
+    /// non-empty pack is checked: CD-TEXT packs must pass their CRC, and
+    /// any other pack must carry the mode and item of CD+G or CD-MIDI.
+    /// Bit rot corrupts the pack headers just as it does the payload, so
+    /// the share of packs passing gives a rough measure of the damage.
+    ///
+    /// This doesn't attempt error correction, so a pack whose only damage
+    /// is in its payload counts as intact unless it's CD-TEXT.
+    pub fn rw_health(&self) -> RwHealth {
+        let mut total = 0;
+        let mut intact = 0;
+
+        for pack in self.rw_packs() {
+            if pack.symbols.iter().all(|symbol| *symbol == 0) {
+                continue;
+            }
+            total += 1;
+
+            let ok = match CdTextPack::from_rw_pack(&pack) {
+                Some(text) => text.crc_valid(),
+                None => matches!(
+                    (pack.mode(), pack.item()),
+                    (GRAPHICS_MODE, GRAPHICS_ITEM) | (MIDI_MODE, MIDI_ITEM)
+                ),
+            };
+            if ok {
+                intact += 1;
+            }
+        }
+
+        if total == 0 {
+            return RwHealth::Absent;
+        }
+        let ratio = intact as f64 / total as f64;
+        if ratio >= GOOD_PACK_RATIO {
+            RwHealth::Good
+        } else {
+            RwHealth::Degraded(ratio)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rw::{RwHealth, RwPack};
+    use crate::subcode;
+    use crate::subcode::testing::{self, pack, sector_with_packs};
+
+    #[test]
+    fn test_rw_packs_round_trip() {
+        let mut packs = [pack(0, 0, 0, &[]); 4];
+        for (i, p) in packs.iter_mut().enumerate() {
+            for (s, symbol) in p.symbols.iter_mut().enumerate() {
+                *symbol = ((i * 24 + s) % 64) as u8;
+            }
+        }
+
+        let sector = sector_with_packs(&packs);
+        assert_eq!(packs, sector.rw_packs());
+    }
+
+    #[test]
+    fn test_rw_packs_are_empty_for_basic_sectors() {
+        let mut data = vec![0xFF; 24];
+        data.extend_from_slice(&[0; 72]);
+        let sector = subcode::Sector::parse(data).unwrap();
+        assert!(sector
+            .rw_packs()
+            .iter()
+            .all(|pack| pack.symbols.iter().all(|s| *s == 0)));
+    }
+
+    #[test]
+    fn test_pack_bytes_round_trip() {
+        let mut bytes = [0; 18];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = (i * 37) as u8;
+        }
+        let p = RwPack::from_bytes(&bytes);
+        assert!(p.symbols.iter().all(|s| *s < 64));
+        assert_eq!(bytes, p.to_bytes());
+    }
+
+    #[test]
+    fn test_pack_fields() {
+        let p = pack(1, 1, 6, &[0xAB, 0xCD]);
+        assert_eq!(1, p.mode());
+        assert_eq!(1, p.item());
+        assert_eq!(6, p.instruction());
+        assert_eq!(0xAB, p.data_bytes()[0]);
+        assert_eq!(0xCD, p.data_bytes()[1]);
+        assert_eq!(0, p.data_bytes()[2]);
+    }
+
+    #[test]
+    fn test_rw_health() {
+        let stream: Vec<u8> = (0..96).map(|i| b'A' + (i % 26) as u8).collect();
+        let packs = testing::cdtext_packs(0x80, 0, 0, &stream);
+        assert_eq!(8, packs.len());
+        assert_eq!(
+            RwHealth::Good,
+            testing::disc_with_cdtext(&packs).rw_health()
+        );
+
+        // Half the packs fail their CRC
+        let mut damaged = packs.clone();
+        for pack in damaged.iter_mut().step_by(2) {
+            pack.bytes[6] ^= 0x10;
+        }
+        assert_eq!(
+            RwHealth::Degraded(0.5),
+            testing::disc_with_cdtext(&damaged).rw_health()
+        );
+
+        let empty = subcode::SubcodeData::parse(vec![0; 192]).unwrap();
+        assert_eq!(RwHealth::Absent, empty.rw_health());
+    }
+
+    #[test]
+    fn test_rw_health_with_graphics() {
+        let graphics = pack(1, 1, 6, &[0xAB]);
+        let noise = pack(5, 3, 0, &[0xFF]);
+        let disc = subcode::SubcodeData {
+            sectors: vec![sector_with_packs(&[graphics, graphics, graphics, noise])],
+        };
+        assert_eq!(RwHealth::Degraded(0.75), disc.rw_health());
+    }
+}
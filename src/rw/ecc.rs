@@ -0,0 +1,258 @@
+//! Reed-Solomon correction of R-W packs using their P parity symbols.
+//!
+//! Each pack is a RS(24, 20) codeword over GF(64), generated by
+//! x^6 + x + 1, with the first symbol as the highest-order coefficient.
+//! The four P parity symbols at the end of the pack allow up to two
+//! damaged symbols to be corrected.
+
+use crate::rw::{RwPack, PACK_SYMBOLS};
+
+/// Number of P parity symbols, and so of syndromes.
+const PARITY_SYMBOLS: usize = 4;
+
+/// Order of the multiplicative group of GF(64).
+const FIELD_ORDER: usize = 63;
+
+#[derive(Debug, Fail, PartialEq, Eq)]
+pub enum EccError {
+    #[fail(display = "pack has too many damaged symbols to correct")]
+    Uncorrectable,
+}
+
+/// Exponent and logarithm tables for GF(64).
+struct Field {
+    exp: [u8; FIELD_ORDER * 2],
+    log: [u8; 64],
+}
+
+impl Field {
+    fn new() -> Field {
+        let mut exp = [0; FIELD_ORDER * 2];
+        let mut log = [0; 64];
+        let mut x: u8 = 1;
+        for i in 0..FIELD_ORDER {
+            exp[i] = x;
+            exp[i + FIELD_ORDER] = x;
+            log[x as usize] = i as u8;
+            x <<= 1;
+            if x & 0x40 != 0 {
+                x ^= 0x43;
+            }
+        }
+
+        Field { exp, log }
+    }
+
+    fn mul(&self, a: u8, b: u8) -> u8 {
+        if a == 0 || b == 0 {
+            return 0;
+        }
+        self.exp[self.log[a as usize] as usize + self.log[b as usize] as usize]
+    }
+
+    fn div(&self, a: u8, b: u8) -> u8 {
+        if a == 0 {
+            return 0;
+        }
+        self.exp[self.log[a as usize] as usize + FIELD_ORDER - self.log[b as usize] as usize]
+    }
+
+    /// Returns the generator raised to `power`.
+    fn alpha(&self, power: usize) -> u8 {
+        self.exp[power % FIELD_ORDER]
+    }
+
+    /// Evaluates a polynomial stored lowest-order coefficient first.
+    fn eval(&self, poly: &[u8], x: u8) -> u8 {
+        poly.iter().rev().fold(0, |acc, c| self.mul(acc, x) ^ c)
+    }
+
+    fn syndromes(&self, symbols: &[u8; PACK_SYMBOLS]) -> [u8; PARITY_SYMBOLS] {
+        let mut syndromes = [0; PARITY_SYMBOLS];
+        for (j, syndrome) in syndromes.iter_mut().enumerate() {
+            let x = self.alpha(j);
+            *syndrome = symbols.iter().fold(0, |acc, s| self.mul(acc, x) ^ s);
+        }
+
+        syndromes
+    }
+}
+
+impl RwPack {
+    /// Corrects up to two damaged symbols using the pack's P parity,
+    /// returning how many were corrected. The pack is left unchanged if
+    /// the damage is too great to correct, or if any symbol doesn't fit
+    /// in six bits, since it can't then have come from the R-W channels.
+    ///
+    /// Since `rw_packs` doesn't undo the interleaving applied to CD+G
+    /// data, this is only meaningful for packs which have been
+    /// deinterleaved or were never interleaved.
+    pub fn correct(&mut self) -> Result<usize, EccError> {
+        if self.symbols.iter().any(|s| *s >= 64) {
+            return Err(EccError::Uncorrectable);
+        }
+        let field = Field::new();
+        let syndromes = field.syndromes(&self.symbols);
+        if syndromes.iter().all(|s| *s == 0) {
+            return Ok(0);
+        }
+
+        // Find the error locator polynomial with Berlekamp-Massey
+        let mut locator = [0; PARITY_SYMBOLS + 1];
+        let mut previous = [0; PARITY_SYMBOLS + 1];
+        locator[0] = 1;
+        previous[0] = 1;
+        let mut errors = 0;
+        let mut shift = 1;
+        let mut last_discrepancy = 1;
+        for n in 0..PARITY_SYMBOLS {
+            let discrepancy = (1..=errors).fold(syndromes[n], |acc, i| {
+                acc ^ field.mul(locator[i], syndromes[n - i])
+            });
+            if discrepancy == 0 {
+                shift += 1;
+                continue;
+            }
+
+            let scale = field.div(discrepancy, last_discrepancy);
+            let saved = locator;
+            for i in shift..=PARITY_SYMBOLS {
+                locator[i] ^= field.mul(scale, previous[i - shift]);
+            }
+            if 2 * errors <= n {
+                errors = n + 1 - errors;
+                previous = saved;
+                last_discrepancy = discrepancy;
+                shift = 1;
+            } else {
+                shift += 1;
+            }
+        }
+        if errors > PARITY_SYMBOLS / 2 {
+            return Err(EccError::Uncorrectable);
+        }
+
+        // The error evaluator is the syndromes times the locator, modulo
+        // x^4; the locator's formal derivative keeps only its odd terms.
+        let mut evaluator = [0; PARITY_SYMBOLS];
+        for (k, value) in evaluator.iter_mut().enumerate() {
+            *value = (0..=k).fold(0, |acc, i| acc ^ field.mul(locator[i], syndromes[k - i]));
+        }
+        let derivative = [locator[1], 0, locator[3]];
+
+        let mut corrected = self.symbols;
+        let mut found = 0;
+        for (i, symbol) in corrected.iter_mut().enumerate() {
+            let power = PACK_SYMBOLS - 1 - i;
+            let inverse = field.alpha(FIELD_ORDER - power % FIELD_ORDER);
+            if field.eval(&locator, inverse) != 0 {
+                continue;
+            }
+
+            let magnitude = field.div(
+                field.eval(&evaluator, inverse),
+                field.eval(&derivative, inverse),
+            );
+            *symbol ^= field.mul(field.alpha(power), magnitude);
+            found += 1;
+        }
+
+        if found != errors || field.syndromes(&corrected).iter().any(|s| *s != 0) {
+            return Err(EccError::Uncorrectable);
+        }
+
+        self.symbols = corrected;
+        Ok(found)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rw::ecc::{EccError, Field, PARITY_SYMBOLS};
+    use crate::rw::{RwPack, PACK_SYMBOLS};
+
+    /// Builds a pack of arbitrary data derived from `seed`, with valid P
+    /// parity.
+    fn encoded(seed: usize) -> RwPack {
+        let field = Field::new();
+        let mut symbols = [0; PACK_SYMBOLS];
+        for (i, symbol) in symbols.iter_mut().enumerate().take(20) {
+            *symbol = ((i * 7 + seed * 13) % 64) as u8;
+        }
+
+        // The generator is (x - 1)(x - a)(x - a^2)(x - a^3), highest
+        // order coefficient first
+        let mut generator = vec![1];
+        for j in 0..PARITY_SYMBOLS {
+            let root = field.alpha(j);
+            let mut next = generator.clone();
+            next.push(0);
+            for (k, c) in generator.iter().enumerate() {
+                next[k + 1] ^= field.mul(*c, root);
+            }
+            generator = next;
+        }
+
+        let mut remainder = [0; PARITY_SYMBOLS];
+        for symbol in &symbols[..20] {
+            let feedback = symbol ^ remainder[0];
+            remainder.rotate_left(1);
+            remainder[PARITY_SYMBOLS - 1] = 0;
+            for (k, r) in remainder.iter_mut().enumerate() {
+                *r ^= field.mul(feedback, generator[k + 1]);
+            }
+        }
+        symbols[20..].copy_from_slice(&remainder);
+
+        RwPack { symbols }
+    }
+
+    #[test]
+    fn test_valid_pack_needs_no_correction() {
+        let mut pack = encoded(1);
+        let original = pack;
+        assert_eq!(Ok(0), pack.correct());
+        assert_eq!(original, pack);
+    }
+
+    #[test]
+    fn test_single_symbol_error_is_corrected() {
+        let original = encoded(2);
+        for position in 0..PACK_SYMBOLS {
+            let mut pack = original;
+            pack.symbols[position] ^= 0x15;
+            assert_eq!(Ok(1), pack.correct());
+            assert_eq!(original, pack);
+        }
+    }
+
+    #[test]
+    fn test_two_symbol_errors_are_corrected() {
+        let original = encoded(3);
+        let mut pack = original;
+        pack.symbols[5] ^= 0x3F;
+        pack.symbols[21] ^= 0x01;
+        assert_eq!(Ok(2), pack.correct());
+        assert_eq!(original, pack);
+    }
+
+    #[test]
+    fn test_uncorrectable_pack_is_left_alone() {
+        let mut pack = encoded(4);
+        pack.symbols[0] ^= 0x01;
+        pack.symbols[1] ^= 0x02;
+        pack.symbols[2] ^= 0x04;
+        let damaged = pack;
+        assert_eq!(Err(EccError::Uncorrectable), pack.correct());
+        assert_eq!(damaged, pack);
+    }
+
+    #[test]
+    fn test_out_of_range_symbols_are_rejected() {
+        let mut pack = encoded(5);
+        pack.symbols[7] = 0x40;
+        let damaged = pack;
+        assert_eq!(Err(EccError::Uncorrectable), pack.correct());
+        assert_eq!(damaged, pack);
+    }
+}
@@ -0,0 +1,278 @@
+use crate::msf::Msf;
+use crate::subcode::q::{catalog_frame, isrc_frame, position_frame};
+use crate::subcode::{Sector, SubcodeData, TrackType, LEAD_OUT_TRACK};
+
+/// Catalog number and ISRC frames replace one position frame in every
+/// this many sectors, as the Red Book requires.
+const MODE_FRAME_INTERVAL: usize = 100;
+
+/// Assembles a synthetic disc with well-formed Q frames, mainly for tests.
+///
+/// Tracks must be added in disc order. No lead-in is generated, so
+/// `TrackType::CdInteractive` tracks read back as `TrackType::Data`.
+/// Any gap between the end of one track and the start of the next
+/// becomes the next track's pregap, and a track overlapping the next one
+/// is cut short.
+#[derive(Clone, Debug, Default)]
+pub struct DiscBuilder {
+    start: Option<Msf>,
+    tracks: Vec<TrackSpec>,
+    catalog: Option<String>,
+    lead_out: usize,
+}
+
+#[derive(Clone, Debug)]
+struct TrackSpec {
+    number: u8,
+    start: Msf,
+    length: usize,
+    control: u8,
+    isrc: Option<String>,
+}
+
+impl DiscBuilder {
+    pub fn new() -> DiscBuilder {
+        DiscBuilder::default()
+    }
+
+    /// Sets the absolute time of the first sector. Any sectors between
+    /// this and the first track's start become that track's pregap.
+    /// Defaults to the start of the first track.
+    pub fn start(mut self, start: Msf) -> DiscBuilder {
+        self.start = Some(start);
+        self
+    }
+
+    /// Adds a track whose index 01 begins at `start` and which runs for
+    /// `length` sectors.
+    pub fn add_track(
+        mut self,
+        number: u8,
+        start: Msf,
+        length: usize,
+        track_type: TrackType,
+    ) -> DiscBuilder {
+        let control = match track_type {
+            TrackType::Audio => 0x0,
+            TrackType::Data | TrackType::CdInteractive => 0x4,
+        };
+        self.tracks.push(TrackSpec {
+            number,
+            start,
+            length,
+            control,
+            isrc: None,
+        });
+        self
+    }
+
+    /// Adds a track with a pregap of `pregap` sectors, placed directly
+    /// after the previous track, or after the disc's start for the first
+    /// track. The start defaults to 00:02:00.
+    pub fn append_track(
+        mut self,
+        number: u8,
+        pregap: usize,
+        length: usize,
+        track_type: TrackType,
+    ) -> DiscBuilder {
+        let end = match self.tracks.last() {
+            Some(spec) => spec.start.frames() + spec.length as u32,
+            None => self.start.get_or_insert(Msf::new(0, 2, 0)).frames(),
+        };
+        let start = Msf::from_frames(end + pregap as u32);
+        self.add_track(number, start, length, track_type)
+    }
+
+    /// Sets the Q channel control bits of an already-added track,
+    /// replacing those implied by its track type.
+    pub fn control(mut self, track: u8, control: u8) -> DiscBuilder {
+        for spec in self.tracks.iter_mut().filter(|spec| spec.number == track) {
+            spec.control = control;
+        }
+        self
+    }
+
+    /// Sets the disc's 13-digit media catalog number.
+    pub fn catalog(mut self, catalog: &str) -> DiscBuilder {
+        self.catalog = Some(catalog.to_string());
+        self
+    }
+
+    /// Sets the ISRC of an already-added track.
+    pub fn isrc(mut self, track: u8, isrc: &str) -> DiscBuilder {
+        for spec in self.tracks.iter_mut().filter(|spec| spec.number == track) {
+            spec.isrc = Some(isrc.to_string());
+        }
+        self
+    }
+
+    /// Appends the given number of lead-out sectors after the last track.
+    pub fn lead_out(mut self, sectors: usize) -> DiscBuilder {
+        self.lead_out = sectors;
+        self
+    }
+
+    /// Produces the disc's subcode. Pregap and lead-out sectors have
+    /// their P channel set, and catalog number and ISRC frames are
+    /// spread through each track's index 01.
+    pub fn build(&self) -> SubcodeData {
+        let mut sectors = vec![];
+        let first = self
+            .start
+            .or_else(|| self.tracks.first().map(|spec| spec.start))
+            .unwrap_or_else(|| Msf::new(0, 2, 0));
+        let mut absolute = first.frames();
+
+        for (i, spec) in self.tracks.iter().enumerate() {
+            let control = spec.control;
+            let start = spec.start.frames();
+            let mut length = spec.length;
+            if let Some(next) = self.tracks.get(i + 1) {
+                length = length.min(next.start.frames().saturating_sub(start) as usize);
+            }
+
+            while absolute < start {
+                sectors.push(sector(
+                    0xFF,
+                    position_frame(
+                        control,
+                        spec.number,
+                        0,
+                        Msf::from_frames(start - absolute),
+                        Msf::from_frames(absolute),
+                    ),
+                ));
+                absolute += 1;
+            }
+
+            for offset in 0..length {
+                let time = Msf::from_frames(absolute);
+                let index = sectors.len();
+                // The first sector of a track always carries its position
+                let slot = if offset > 0 {
+                    Some(index % MODE_FRAME_INTERVAL)
+                } else {
+                    None
+                };
+                let q = match (slot, &self.catalog, &spec.isrc) {
+                    (Some(0), Some(catalog), _) => catalog_frame(catalog, time.frame),
+                    (Some(slot), _, Some(isrc)) if slot == MODE_FRAME_INTERVAL / 2 => {
+                        isrc_frame(isrc, time.frame)
+                    }
+                    _ => position_frame(
+                        control,
+                        spec.number,
+                        1,
+                        Msf::from_frames(offset as u32),
+                        time,
+                    ),
+                };
+                sectors.push(sector(0, q));
+                absolute += 1;
+            }
+        }
+
+        for offset in 0..self.lead_out {
+            sectors.push(sector(
+                0xFF,
+                position_frame(
+                    0,
+                    LEAD_OUT_TRACK,
+                    1,
+                    Msf::from_frames(offset as u32),
+                    Msf::from_frames(absolute),
+                ),
+            ));
+            absolute += 1;
+        }
+
+        SubcodeData { sectors }
+    }
+}
+
+/// Builds a sector with the given P fill byte and Q frame, and empty R-W.
+pub(crate) fn sector(p: u8, q: Vec<u8>) -> Sector {
+    let mut data = vec![p; 12];
+    data.extend_from_slice(&q);
+    data.extend_from_slice(&[0; 72]);
+    // We unwrap here because the data is always exactly 96 bytes
+    Sector::parse(data).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::msf::Msf;
+    use crate::subcode::{DiscBuilder, SubcodeType, TrackType};
+
+    #[test]
+    fn test_two_track_disc_round_trips() {
+        let disc = DiscBuilder::new()
+            .start(Msf::new(0, 0, 0))
+            .add_track(1, Msf::new(0, 2, 0), 300, TrackType::Audio)
+            .add_track(2, Msf::new(0, 8, 0), 200, TrackType::Data)
+            .catalog("0123456789012")
+            .isrc(1, "USABC9900001")
+            .lead_out(10)
+            .build();
+
+        // 150 pregap + 300 + 150 pregap + 200 + 10 lead-out
+        assert_eq!(810, disc.sectors.len());
+        assert!(disc.channel_iter(SubcodeType::Q).all(|q| q.q_crc_valid()));
+        assert_eq!(Some("0123456789012".to_string()), disc.catalog());
+
+        let tracks = disc.tracks();
+        assert_eq!(2, tracks.len());
+        assert_eq!(1, tracks[0].number);
+        assert_eq!(TrackType::Audio, tracks[0].track_type);
+        assert_eq!(Some(0), tracks[0].pregap_start);
+        assert_eq!(150, tracks[0].start);
+        assert_eq!(450, tracks[0].end);
+        assert_eq!(Some("USABC9900001".to_string()), tracks[0].isrc);
+
+        assert_eq!(2, tracks[1].number);
+        assert_eq!(TrackType::Data, tracks[1].track_type);
+        assert_eq!(Some(450), tracks[1].pregap_start);
+        assert_eq!(600, tracks[1].start);
+        assert_eq!(800, tracks[1].end);
+        assert_eq!(None, tracks[1].isrc);
+
+        assert_eq!(
+            Some(Msf::new(0, 8, 0)),
+            disc.sectors[600].codes[1].q_absolute()
+        );
+    }
+
+    #[test]
+    fn test_overlapping_tracks_are_cut_short() {
+        let disc = DiscBuilder::new()
+            .add_track(1, Msf::new(0, 2, 0), 50, TrackType::Audio)
+            .add_track(2, Msf::new(0, 2, 30), 10, TrackType::Audio)
+            .build();
+
+        assert_eq!(40, disc.sectors.len());
+        assert_eq!(30, disc.tracks()[1].start);
+    }
+
+    #[test]
+    fn test_appended_tracks_follow_their_pregaps() {
+        let disc = DiscBuilder::new()
+            .append_track(1, 0, 10, TrackType::Audio)
+            .append_track(2, 3, 5, TrackType::Data)
+            .control(2, 0x6)
+            .lead_out(2)
+            .build();
+
+        assert_eq!(20, disc.sectors.len());
+        assert_eq!(
+            Some(Msf::new(0, 2, 0)),
+            disc.sectors[0].codes[1].q_absolute()
+        );
+        let tracks = disc.tracks();
+        assert_eq!(None, tracks[0].pregap_start);
+        assert_eq!(Some(10), tracks[1].pregap_start);
+        assert_eq!(13, tracks[1].start);
+        assert_eq!(18, tracks[1].end);
+        assert_eq!(Some(0x61), disc.sectors[13].codes[1].q_adr_control_byte());
+    }
+}
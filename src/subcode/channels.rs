@@ -0,0 +1,258 @@
+use std::fmt;
+use std::iter::FromIterator;
+
+use crate::subcode::{Sector, SubcodeData, SubcodeType};
+
+/// A set of subcode channels.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ChannelSet {
+    bits: u8,
+}
+
+impl ChannelSet {
+    /// Creates an empty set.
+    pub fn new() -> ChannelSet {
+        ChannelSet::default()
+    }
+
+    /// Creates a set containing all eight channels.
+    pub fn all() -> ChannelSet {
+        ChannelSet { bits: 0xFF }
+    }
+
+    pub fn insert(&mut self, channel: SubcodeType) {
+        self.bits |= 1 << channel.index();
+    }
+
+    pub fn remove(&mut self, channel: SubcodeType) {
+        self.bits &= !(1 << channel.index());
+    }
+
+    pub fn contains(&self, channel: SubcodeType) -> bool {
+        self.bits & (1 << channel.index()) != 0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits == 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.bits.count_ones() as usize
+    }
+
+    /// Returns the channels in either set.
+    pub fn union(self, other: ChannelSet) -> ChannelSet {
+        ChannelSet {
+            bits: self.bits | other.bits,
+        }
+    }
+
+    /// Returns the channels in the set, in P through W order.
+    pub fn iter(&self) -> impl Iterator<Item = SubcodeType> {
+        let set = *self;
+        (0..8)
+            .filter_map(SubcodeType::from_index)
+            .filter(move |channel| set.contains(*channel))
+    }
+}
+
+impl FromIterator<SubcodeType> for ChannelSet {
+    fn from_iter<I: IntoIterator<Item = SubcodeType>>(iter: I) -> ChannelSet {
+        let mut set = ChannelSet::new();
+        for channel in iter {
+            set.insert(channel);
+        }
+        set
+    }
+}
+
+/// Lists the channels in the set by letter, such as `PQ`.
+impl fmt::Display for ChannelSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for channel in self.iter() {
+            f.write_str(&channel.to_string())?;
+        }
+        Ok(())
+    }
+}
+
+impl Sector {
+    /// Returns every channel for which this sector contains data, as
+    /// `contains_data_in_channels` does, but as a `ChannelSet`.
+    pub fn contains_data_in_channels_set(&self) -> ChannelSet {
+        self.contains_data_in_channels().into_iter().collect()
+    }
+}
+
+impl SubcodeData {
+    /// Returns every channel which carries data in at least one sector.
+    pub fn populated_channels(&self) -> ChannelSet {
+        let mut set = ChannelSet::new();
+        for sector in &self.sectors {
+            for code in sector.codes.iter().filter(|code| !code.is_empty()) {
+                set.insert(code.channel);
+            }
+            if set == ChannelSet::all() {
+                break;
+            }
+        }
+
+        set
+    }
+
+    /// Returns the fraction of bits set in each channel across the whole
+    /// disc, from 0.0 to 1.0, in P through W order. A channel that's
+    /// almost always empty or almost always full probably isn't carrying
+    /// real data. Every channel is 0.0 on an empty disc.
+    pub fn channel_bit_density(&self) -> [f64; 8] {
+        let mut counts = [0; 8];
+        for sector in &self.sectors {
+            for (i, code) in sector.codes.iter().enumerate() {
+                counts[i] += code
+                    .data
+                    .iter()
+                    .map(|byte| byte.count_ones() as usize)
+                    .sum::<usize>();
+            }
+        }
+
+        bit_density(counts, self.sectors.len())
+    }
+
+    /// Serializes the disc as `to_bytes` does, but with every channel not
+    /// in `channels` zeroed. Each sector still takes 96 bytes, so the
+    /// result can be read back with `parse`.
+    pub fn to_bytes_channels(&self, channels: ChannelSet) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.sectors.len() * 96);
+        for sector in &self.sectors {
+            for code in sector.codes.iter() {
+                if channels.contains(code.channel) {
+                    bytes.extend_from_slice(&code.data);
+                } else {
+                    bytes.extend_from_slice(&[0; 12]);
+                }
+            }
+        }
+
+        bytes
+    }
+}
+
+/// Turns per-channel counts of set bits into the fraction of all bits
+/// they represent.
+pub(crate) fn bit_density(counts: [usize; 8], sectors: usize) -> [f64; 8] {
+    let mut density = [0.0; 8];
+    if sectors == 0 {
+        return density;
+    }
+    for (density, count) in density.iter_mut().zip(counts.iter()) {
+        *density = *count as f64 / (sectors * 96) as f64;
+    }
+
+    density
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::subcode::{ChannelSet, SubcodeData, SubcodeType};
+
+    #[test]
+    fn test_channel_set() {
+        let mut set = ChannelSet::new();
+        assert!(set.is_empty());
+        set.insert(SubcodeType::W);
+        set.insert(SubcodeType::P);
+        set.insert(SubcodeType::P);
+        assert_eq!(2, set.len());
+        assert!(set.contains(SubcodeType::P));
+        assert!(!set.contains(SubcodeType::Q));
+        assert_eq!(
+            vec![SubcodeType::P, SubcodeType::W],
+            set.iter().collect::<Vec<_>>()
+        );
+
+        set.remove(SubcodeType::W);
+        let q: ChannelSet = vec![SubcodeType::Q].into_iter().collect();
+        assert_eq!(
+            vec![SubcodeType::P, SubcodeType::Q],
+            set.union(q).iter().collect::<Vec<_>>()
+        );
+        assert_eq!(8, ChannelSet::all().len());
+    }
+
+    #[test]
+    fn test_channel_set_display() {
+        let pq: ChannelSet = vec![SubcodeType::Q, SubcodeType::P].into_iter().collect();
+        assert_eq!("PQ", pq.to_string());
+        assert_eq!("PQRSTUVW", ChannelSet::all().to_string());
+        assert_eq!("", ChannelSet::new().to_string());
+
+        let mut data = vec![0; 12];
+        data.extend_from_slice(&[1; 12]);
+        data.extend_from_slice(&[0; 60]);
+        data.extend_from_slice(&[1; 12]);
+        let sector = SubcodeData::parse(data).unwrap().sectors.remove(0);
+        assert_eq!("QW", sector.contains_data_in_channels_set().to_string());
+    }
+
+    #[test]
+    fn test_populated_channels() {
+        // Only Q in the first two sectors, and R-W in the third
+        let mut data = vec![];
+        for _ in 0..2 {
+            data.extend_from_slice(&[0; 12]);
+            data.extend_from_slice(&[1; 12]);
+            data.extend_from_slice(&[0; 72]);
+        }
+        data.extend_from_slice(&[0; 24]);
+        data.extend_from_slice(&[1; 72]);
+
+        let set = SubcodeData::parse(data).unwrap().populated_channels();
+        assert_eq!(7, set.len());
+        assert!(!set.contains(SubcodeType::P));
+        assert!(set.contains(SubcodeType::Q));
+        for channel in &[
+            SubcodeType::R,
+            SubcodeType::S,
+            SubcodeType::T,
+            SubcodeType::U,
+            SubcodeType::V,
+            SubcodeType::W,
+        ] {
+            assert!(set.contains(*channel));
+        }
+    }
+
+    #[test]
+    fn test_channel_bit_density() {
+        // P is full in one of the two sectors, and Q has one byte set
+        let mut data = vec![0xFF; 12];
+        data.extend_from_slice(&[0x0F; 1]);
+        data.extend_from_slice(&[0; 83 + 96]);
+        let density = SubcodeData::parse(data).unwrap().channel_bit_density();
+        assert_eq!(0.5, density[0]);
+        assert_eq!(4.0 / 192.0, density[1]);
+        assert!(density[2..].iter().all(|d| *d == 0.0));
+        assert_eq!([0.0; 8], SubcodeData::new().channel_bit_density());
+    }
+
+    #[test]
+    fn test_to_bytes_channels() {
+        let disc = SubcodeData::parse(vec![0xA5; 96 * 3]).unwrap();
+        let q_only: ChannelSet = vec![SubcodeType::Q].into_iter().collect();
+        let bytes = disc.to_bytes_channels(q_only);
+        assert_eq!(96 * 3, bytes.len());
+
+        let masked = SubcodeData::parse(bytes).unwrap();
+        for sector in masked.iter() {
+            for code in sector.channels() {
+                if code.channel == SubcodeType::Q {
+                    assert_eq!(vec![0xA5; 12], code.data);
+                } else {
+                    assert!(code.is_empty());
+                }
+            }
+        }
+        assert_eq!(disc.to_bytes(), disc.to_bytes_channels(ChannelSet::all()));
+    }
+}
@@ -0,0 +1,117 @@
+use std::collections::BTreeMap;
+
+use crate::cdtext::CdTextPack;
+use crate::rw::{GRAPHICS_ITEM, GRAPHICS_MODE};
+use crate::subcode::{Sector, SubcodeData, SubcodeType};
+
+/// A rough classification of what a sector's subcode carries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SectorClass {
+    /// An audio sector with only P and Q data.
+    BasicAudio,
+    /// A data sector with only P and Q data.
+    Data,
+    /// A sector whose R-W channels carry CD+G graphics.
+    CdGraphics,
+    /// A sector whose R-W channels carry CD-TEXT.
+    CdText,
+    /// A sector which fits none of the other classes, such as one with a
+    /// damaged Q channel or unrecognised R-W data.
+    Unknown,
+}
+
+impl Sector {
+    /// Classifies the sector by its contents. R-W data takes precedence:
+    /// a sector holding any CD-TEXT pack with a valid CRC is `CdText`, and
+    /// otherwise one holding any CD+G pack is `CdGraphics`. Sectors with
+    /// empty R-W channels are `Data` or `BasicAudio` according to the
+    /// control bits of their Q frame, which must pass its CRC.
+    pub fn classify(&self) -> SectorClass {
+        if !self.contains_basic_data_only() {
+            let packs = self.rw_packs();
+            if packs
+                .iter()
+                .filter_map(CdTextPack::from_rw_pack)
+                .any(|pack| pack.crc_valid())
+            {
+                return SectorClass::CdText;
+            }
+            if packs
+                .iter()
+                .any(|pack| pack.mode() == GRAPHICS_MODE && pack.item() == GRAPHICS_ITEM)
+            {
+                return SectorClass::CdGraphics;
+            }
+            return SectorClass::Unknown;
+        }
+
+        let q = self.channel(SubcodeType::Q);
+        if !q.q_crc_valid() {
+            return SectorClass::Unknown;
+        }
+        match q.q_control() {
+            Some(control) if control.data => SectorClass::Data,
+            Some(_) => SectorClass::BasicAudio,
+            None => SectorClass::Unknown,
+        }
+    }
+}
+
+impl SubcodeData {
+    /// Counts the sectors in each class; see `Sector::classify`. Classes
+    /// with no sectors are left out.
+    pub fn class_histogram(&self) -> BTreeMap<SectorClass, usize> {
+        let mut histogram = BTreeMap::new();
+        for sector in &self.sectors {
+            *histogram.entry(sector.classify()).or_insert(0) += 1;
+        }
+
+        histogram
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::msf::Msf;
+    use crate::subcode::testing;
+    use crate::subcode::{DiscBuilder, SectorClass, TrackType};
+
+    #[test]
+    fn test_classify() {
+        let audio = testing::sector_with_q(testing::position_frame(
+            0x0,
+            1,
+            1,
+            Msf::new(0, 0, 0),
+            Msf::new(0, 2, 0),
+        ));
+        assert_eq!(SectorClass::BasicAudio, audio.classify());
+
+        let graphics = testing::pack(1, 1, 6, &[0xAB]);
+        let sector = testing::sector_with_packs(&[graphics; 4]);
+        assert_eq!(SectorClass::CdGraphics, sector.classify());
+
+        let noise = testing::pack(5, 3, 0, &[0xFF]);
+        let sector = testing::sector_with_packs(&[noise; 4]);
+        assert_eq!(SectorClass::Unknown, sector.classify());
+    }
+
+    #[test]
+    fn test_class_histogram() {
+        let mut disc = DiscBuilder::new()
+            .append_track(1, 0, 6, TrackType::Audio)
+            .append_track(2, 0, 4, TrackType::Data)
+            .build();
+        let packs = testing::cdtext_packs(0x80, 0, 0, b"Album\0");
+        disc.sectors
+            .extend(testing::disc_with_cdtext(&packs).sectors);
+        disc.sectors[2].codes[1].data[3] ^= 0xFF;
+
+        let histogram = disc.class_histogram();
+        assert_eq!(Some(&5), histogram.get(&SectorClass::BasicAudio));
+        assert_eq!(Some(&4), histogram.get(&SectorClass::Data));
+        assert_eq!(Some(&1), histogram.get(&SectorClass::CdText));
+        assert_eq!(Some(&1), histogram.get(&SectorClass::Unknown));
+        assert_eq!(None, histogram.get(&SectorClass::CdGraphics));
+    }
+}
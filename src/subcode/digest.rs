@@ -0,0 +1,83 @@
+use std::collections::BTreeMap;
+
+use sha2::{Digest, Sha256};
+
+use crate::subcode::{Subcode, SubcodeData, SubcodeType};
+
+impl SubcodeData {
+    /// Computes a SHA-256 digest of the Q channel alone, concatenated in
+    /// sector order. Since the P and R-W channels are left out, two dumps
+    /// of the same disc which differ only in R-W noise hash the same,
+    /// making this a stable identifier for the disc's layout.
+    pub fn q_channel_digest(&self) -> [u8; 32] {
+        digest(self.channel_iter(SubcodeType::Q))
+    }
+
+    /// Computes a SHA-256 digest of each track's Q channel, keyed by track
+    /// number. Each covers the track from index 01 to its end, leaving out
+    /// the pregap, so a track's digest is unaffected by damage elsewhere
+    /// on the disc.
+    pub fn track_q_digests(&self) -> BTreeMap<u8, [u8; 32]> {
+        self.tracks()
+            .into_iter()
+            .map(|track| {
+                let sectors = &self.sectors[track.start..track.end];
+                let digest = digest(sectors.iter().map(|s| s.channel(SubcodeType::Q)));
+                (track.number, digest)
+            })
+            .collect()
+    }
+}
+
+fn digest<'a, I: Iterator<Item = &'a Subcode>>(codes: I) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for q in codes {
+        hasher.update(&q.data);
+    }
+
+    hasher.finalize().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::msf::Msf;
+    use crate::subcode::{DiscBuilder, TrackType};
+
+    #[test]
+    fn test_q_channel_digest() {
+        let disc = || {
+            DiscBuilder::new()
+                .add_track(1, Msf::new(0, 2, 0), 20, TrackType::Audio)
+                .build()
+        };
+        let first = disc();
+        let mut second = disc();
+        assert_eq!(first.q_channel_digest(), second.q_channel_digest());
+
+        // R-W noise doesn't matter, but a change to Q does
+        second.sectors[3].codes[4].data[0] = 0xFF;
+        assert_eq!(first.q_channel_digest(), second.q_channel_digest());
+        second.sectors[3].codes[1].data[7] ^= 0x01;
+        assert_ne!(first.q_channel_digest(), second.q_channel_digest());
+    }
+
+    #[test]
+    fn test_track_q_digests() {
+        let disc = || {
+            DiscBuilder::new()
+                .add_track(1, Msf::new(0, 2, 0), 20, TrackType::Audio)
+                .add_track(2, Msf::new(0, 2, 20), 20, TrackType::Audio)
+                .build()
+        };
+        let first = disc().track_q_digests();
+        assert_eq!(vec![1, 2], first.keys().cloned().collect::<Vec<_>>());
+        assert_ne!(first[&1], first[&2]);
+
+        // Damage to track 2 leaves track 1's digest alone
+        let mut damaged = disc();
+        damaged.sectors[25].codes[1].data[7] ^= 0x01;
+        let second = damaged.track_q_digests();
+        assert_eq!(first[&1], second[&1]);
+        assert_ne!(first[&2], second[&2]);
+    }
+}
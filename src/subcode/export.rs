@@ -0,0 +1,294 @@
+use std::io::{self, Write};
+
+use crate::msf::Msf;
+use crate::subcode::{SubcodeData, TrackType, LEAD_OUT_TRACK};
+
+/// Bytes per sector in the raw image the exported formats refer to.
+const IMAGE_SECTOR_SIZE: usize = 2352;
+
+impl SubcodeData {
+    /// Renders the disc's layout as a cdrdao `.toc` file referring to
+    /// `image_filename`, a raw 2352-byte-per-sector image whose sectors
+    /// line up with this subcode.
+    ///
+    /// Audio tracks are emitted as `TRACK AUDIO`, data tracks as
+    /// `TRACK MODE1_RAW` and CD-i tracks as `TRACK MODE2_RAW`. A pregap is
+    /// only described when the dump contains it, by starting the track's
+    /// data at the pregap and marking index 01 with `START`.
+    pub fn to_cdrdao_toc(&self, image_filename: &str) -> String {
+        let tracks = self.tracks();
+        let mut toc = String::new();
+
+        if tracks.iter().all(|t| t.track_type == TrackType::Audio) {
+            toc.push_str("CD_DA\n");
+        } else if tracks
+            .iter()
+            .any(|t| t.track_type == TrackType::CdInteractive)
+        {
+            toc.push_str("CD_I\n");
+        } else {
+            toc.push_str("CD_ROM\n");
+        }
+        if let Some(catalog) = self.catalog() {
+            toc.push_str(&format!("CATALOG \"{}\"\n", catalog));
+        }
+
+        for track in &tracks {
+            toc.push_str(&format!("\n// Track {}\n", track.number));
+            match track.track_type {
+                TrackType::Audio => toc.push_str("TRACK AUDIO\n"),
+                TrackType::Data => toc.push_str("TRACK MODE1_RAW\n"),
+                TrackType::CdInteractive => toc.push_str("TRACK MODE2_RAW\n"),
+            }
+            if track.control.copy_permitted {
+                toc.push_str("COPY\n");
+            } else {
+                toc.push_str("NO COPY\n");
+            }
+
+            let first = track.first_sector();
+            let length = Msf::from_frames((track.end - first) as u32);
+            match track.track_type {
+                TrackType::Audio => {
+                    if track.control.pre_emphasis {
+                        toc.push_str("PRE_EMPHASIS\n");
+                    } else {
+                        toc.push_str("NO PRE_EMPHASIS\n");
+                    }
+                    if track.control.four_channel {
+                        toc.push_str("FOUR_CHANNEL_AUDIO\n");
+                    } else {
+                        toc.push_str("TWO_CHANNEL_AUDIO\n");
+                    }
+                    if let Some(ref isrc) = track.isrc {
+                        toc.push_str(&format!("ISRC \"{}\"\n", isrc));
+                    }
+                    toc.push_str(&format!(
+                        "FILE \"{}\" #{} {}\n",
+                        image_filename,
+                        first * IMAGE_SECTOR_SIZE,
+                        length
+                    ));
+                }
+                TrackType::Data | TrackType::CdInteractive => {
+                    toc.push_str(&format!(
+                        "DATAFILE \"{}\" #{} {}\n",
+                        image_filename,
+                        first * IMAGE_SECTOR_SIZE,
+                        length
+                    ));
+                }
+            }
+            if track.start > first {
+                toc.push_str(&format!(
+                    "START {}\n",
+                    Msf::from_frames((track.start - first) as u32)
+                ));
+            }
+        }
+
+        toc
+    }
+
+    /// Renders the disc's layout as a cue sheet referring to
+    /// `image_filename`, a raw 2352-byte-per-sector image whose sectors
+    /// line up with this subcode. See `write_cue_sheet`.
+    pub fn to_cue_sheet(&self, image_filename: &str) -> String {
+        let mut out = vec![];
+        // We unwrap here because writing to a Vec can't fail, and
+        // everything written is valid UTF-8
+        self.write_cue_sheet(image_filename, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    /// Writes the disc's layout as a cue sheet to `w`, without building
+    /// it in memory first.
+    ///
+    /// Audio tracks are emitted as `AUDIO`, data tracks as `MODE1/2352`
+    /// and CD-i tracks as `CDI/2352`. `FLAGS` lists the track's
+    /// copy-permitted, pre-emphasis and four-channel bits, if any are set.
+    /// An `INDEX 00` is only written when the dump contains the track's
+    /// pregap.
+    pub fn write_cue_sheet<W: Write>(&self, image_filename: &str, w: &mut W) -> io::Result<()> {
+        if let Some(catalog) = self.catalog() {
+            writeln!(w, "CATALOG {}", catalog)?;
+        }
+        writeln!(w, "FILE \"{}\" BINARY", image_filename)?;
+
+        for track in self.tracks() {
+            let mode = match track.track_type {
+                TrackType::Audio => "AUDIO",
+                TrackType::Data => "MODE1/2352",
+                TrackType::CdInteractive => "CDI/2352",
+            };
+            writeln!(w, "  TRACK {:02} {}", track.number, mode)?;
+
+            let mut flags = vec![];
+            if track.control.copy_permitted {
+                flags.push("DCP");
+            }
+            if track.control.four_channel {
+                flags.push("4CH");
+            }
+            if track.control.pre_emphasis && track.track_type == TrackType::Audio {
+                flags.push("PRE");
+            }
+            if !flags.is_empty() {
+                writeln!(w, "    FLAGS {}", flags.join(" "))?;
+            }
+            if let Some(ref isrc) = track.isrc {
+                writeln!(w, "    ISRC {}", isrc)?;
+            }
+            if let Some(pregap_start) = track.pregap_start {
+                writeln!(w, "    INDEX 00 {}", Msf::from_frames(pregap_start as u32))?;
+            }
+            writeln!(w, "    INDEX 01 {}", Msf::from_frames(track.start as u32))?;
+        }
+
+        Ok(())
+    }
+
+    /// Renders the decoded Q position of every sector as CSV, one row per
+    /// sector after a header row. The control nibble is written in hex.
+    /// Fields which the sector's Q frame doesn't carry, such as the
+    /// position in an ADR-2 frame, are left blank; the lead-out's track
+    /// number is written as `AA`.
+    pub fn positions_csv(&self) -> String {
+        let mut csv = String::from("sector,track,index,relative,absolute,control,crc_ok\n");
+
+        for (i, sector) in self.sectors.iter().enumerate() {
+            let row = match sector.q() {
+                Some(q) => {
+                    let track = match q.track {
+                        Some(LEAD_OUT_TRACK) => "AA".to_string(),
+                        track => optional(track),
+                    };
+                    format!(
+                        "{},{},{},{},{},{:X},{}\n",
+                        i,
+                        track,
+                        optional(q.index),
+                        optional(q.relative),
+                        optional(q.absolute),
+                        q.control.to_nibble(),
+                        q.crc_valid
+                    )
+                }
+                None => format!("{},,,,,,false\n", i),
+            };
+            csv.push_str(&row);
+        }
+
+        csv
+    }
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::subcode::testing;
+    use crate::subcode::{DiscBuilder, TrackType};
+
+    #[test]
+    fn test_to_cdrdao_toc() {
+        let mut disc = DiscBuilder::new()
+            .append_track(1, 0, 10, TrackType::Audio)
+            .control(1, 0x2)
+            .append_track(2, 3, 5, TrackType::Audio)
+            .control(2, 0x1)
+            .lead_out(2)
+            .build();
+        disc.sectors[2] = testing::sector_with_q(testing::catalog_frame("1234567890123", 2));
+        disc.sectors[15] = testing::sector_with_q(testing::isrc_frame("GBXYZ0000001", 15));
+
+        let expected = "CD_DA
+CATALOG \"1234567890123\"
+
+// Track 1
+TRACK AUDIO
+COPY
+NO PRE_EMPHASIS
+TWO_CHANNEL_AUDIO
+FILE \"disc.bin\" #0 00:00:10
+
+// Track 2
+TRACK AUDIO
+NO COPY
+PRE_EMPHASIS
+TWO_CHANNEL_AUDIO
+ISRC \"GBXYZ0000001\"
+FILE \"disc.bin\" #23520 00:00:08
+START 00:00:03
+";
+        assert_eq!(expected, disc.to_cdrdao_toc("disc.bin"));
+    }
+
+    #[test]
+    fn test_to_cdrdao_toc_data_track() {
+        let disc = DiscBuilder::new()
+            .append_track(1, 0, 4, TrackType::Data)
+            .build();
+
+        let expected = "CD_ROM
+
+// Track 1
+TRACK MODE1_RAW
+NO COPY
+DATAFILE \"disc.bin\" #0 00:00:04
+";
+        assert_eq!(expected, disc.to_cdrdao_toc("disc.bin"));
+    }
+
+    #[test]
+    fn test_write_cue_sheet() {
+        let mut disc = DiscBuilder::new()
+            .append_track(1, 0, 10, TrackType::Audio)
+            .control(1, 0x2)
+            .append_track(2, 3, 80, TrackType::Data)
+            .lead_out(2)
+            .build();
+        disc.sectors[2] = testing::sector_with_q(testing::catalog_frame("1234567890123", 2));
+        disc.sectors[4] = testing::sector_with_q(testing::isrc_frame("GBXYZ0000001", 4));
+
+        let expected = "CATALOG 1234567890123
+FILE \"disc.bin\" BINARY
+  TRACK 01 AUDIO
+    FLAGS DCP
+    ISRC GBXYZ0000001
+    INDEX 01 00:00:00
+  TRACK 02 MODE1/2352
+    INDEX 00 00:00:10
+    INDEX 01 00:00:13
+";
+        let mut out = vec![];
+        disc.write_cue_sheet("disc.bin", &mut out).unwrap();
+        assert_eq!(expected.as_bytes(), &out[..]);
+        assert_eq!(expected, disc.to_cue_sheet("disc.bin"));
+    }
+
+    #[test]
+    fn test_positions_csv() {
+        let mut disc = DiscBuilder::new()
+            .append_track(1, 2, 3, TrackType::Data)
+            .lead_out(1)
+            .build();
+        disc.sectors[3] = testing::sector_with_q(testing::catalog_frame("1234567890123", 3));
+        disc.sectors[4].codes[1].data[10] ^= 0xFF;
+
+        let csv = disc.positions_csv();
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(7, rows.len());
+        assert_eq!(
+            "sector,track,index,relative,absolute,control,crc_ok",
+            rows[0]
+        );
+        assert_eq!("0,1,0,00:00:02,00:02:00,4,true", rows[1]);
+        assert_eq!("2,1,1,00:00:00,00:02:02,4,true", rows[3]);
+        assert_eq!("3,,,,,0,true", rows[4]);
+        assert_eq!("4,1,1,00:00:02,00:02:04,4,false", rows[5]);
+        assert_eq!("5,AA,1,00:00:00,00:02:05,0,true", rows[6]);
+    }
+}
@@ -0,0 +1,62 @@
+use crate::subcode::{SubcodeData, SubcodeType};
+
+/// Set in a flag byte when the sector's P channel signals a pause.
+pub const FLAG_PAUSE: u8 = 0x80;
+
+/// Set in a flag byte when any of the R through W channels hold data.
+pub const FLAG_EXTENDED_DATA: u8 = 0x40;
+
+/// The bits of a flag byte holding the Q channel's ADR nibble.
+pub const FLAG_ADR_MASK: u8 = 0x0F;
+
+impl SubcodeData {
+    /// Summarises every sector as a single byte, for consumers that only
+    /// need a cheap overview such as a timeline display. Each byte
+    /// combines `FLAG_PAUSE`, `FLAG_EXTENDED_DATA` and the Q frame's
+    /// ADR in the bits covered by `FLAG_ADR_MASK`.
+    pub fn to_flag_stream(&self) -> Vec<u8> {
+        self.sectors
+            .iter()
+            .map(|sector| {
+                let mut flags = 0;
+                // Unwrapping is safe since these are always the P and Q channels
+                if sector.channel(SubcodeType::P).p_pause().unwrap() {
+                    flags |= FLAG_PAUSE;
+                }
+                if !sector.contains_basic_data_only() {
+                    flags |= FLAG_EXTENDED_DATA;
+                }
+                flags | (sector.channel(SubcodeType::Q).q_adr().unwrap() & FLAG_ADR_MASK)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::msf::Msf;
+    use crate::subcode;
+    use crate::subcode::testing;
+
+    #[test]
+    fn test_flag_stream() {
+        let audio = testing::sector_with_q(testing::position_frame(
+            0,
+            1,
+            1,
+            Msf::new(0, 0, 0),
+            Msf::new(0, 2, 0),
+        ));
+        let pregap = testing::sector(
+            0xFF,
+            testing::position_frame(0, 2, 0, Msf::new(0, 0, 1), Msf::new(0, 2, 1)),
+        );
+        let mut catalog = testing::sector_with_q(testing::catalog_frame("0000000000000", 2));
+        catalog.codes[5].data[0] = 1;
+
+        let disc = subcode::SubcodeData {
+            sectors: vec![audio, pregap, catalog],
+        };
+        assert_eq!(vec![0x01, 0x81, 0x42], disc.to_flag_stream());
+    }
+}
@@ -0,0 +1,587 @@
+use std::io::{self, Read, Write};
+
+use crate::subcode::{InvalidDataError, Sector, SubcodeData, SubcodeType};
+
+/// Size of the user data portion of a raw CD sector.
+pub(crate) const RAW_SECTOR_SIZE: usize = 2352;
+
+/// Size of the sync pattern which starts a raw data sector.
+const SYNC_SIZE: usize = 12;
+
+/// Size of the subcode which trails the user data in a 2448-byte sector.
+pub(crate) const SUBCODE_SIZE: usize = 96;
+
+/// Size of a raw CD sector with its subcode appended.
+pub(crate) const RAW_SECTOR_WITH_SUBCODE_SIZE: usize = RAW_SECTOR_SIZE + SUBCODE_SIZE;
+
+/// How the 96 bytes of subcode in each sector are arranged.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SubcodeLayout {
+    /// Each channel's 12 bytes are stored contiguously, in P through W
+    /// order. This is the layout `SubcodeData::parse` expects.
+    Deinterleaved,
+    /// Each byte holds one bit from every channel for a single frame, with
+    /// P in the most significant bit and W in the least. This is the raw
+    /// form returned by most drives.
+    Interleaved,
+}
+
+impl SubcodeLayout {
+    /// Converts a single sector's subcode from this layout to `to`.
+    pub fn convert(self, sector: &[u8; SUBCODE_SIZE], to: SubcodeLayout) -> [u8; SUBCODE_SIZE] {
+        match (self, to) {
+            (SubcodeLayout::Interleaved, SubcodeLayout::Deinterleaved) => deinterleave(sector),
+            (SubcodeLayout::Deinterleaved, SubcodeLayout::Interleaved) => interleave(sector),
+            _ => *sector,
+        }
+    }
+}
+
+/// Which parts of each sector's main channel a SCSI READ CD command
+/// returned ahead of the subcode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ReadCdMainChannel {
+    /// The whole 2352-byte sector, including the 12-byte sync pattern
+    /// on data sectors. This is the only selection valid for audio.
+    Full,
+    /// The 2340 bytes following the sync pattern: the header, user data
+    /// and EDC/ECC of a data sector.
+    SyncStripped,
+}
+
+impl ReadCdMainChannel {
+    /// Returns the number of main channel bytes in each sector.
+    pub fn size(self) -> usize {
+        match self {
+            ReadCdMainChannel::Full => RAW_SECTOR_SIZE,
+            ReadCdMainChannel::SyncStripped => RAW_SECTOR_SIZE - SYNC_SIZE,
+        }
+    }
+}
+
+fn deinterleave(sector: &[u8; SUBCODE_SIZE]) -> [u8; SUBCODE_SIZE] {
+    let mut out = [0; SUBCODE_SIZE];
+    for (frame, byte) in sector.iter().enumerate() {
+        for channel in 0..8 {
+            if byte & (0x80 >> channel) != 0 {
+                out[channel * 12 + frame / 8] |= 0x80 >> (frame % 8);
+            }
+        }
+    }
+
+    out
+}
+
+fn interleave(sector: &[u8; SUBCODE_SIZE]) -> [u8; SUBCODE_SIZE] {
+    let mut out = [0; SUBCODE_SIZE];
+    for (frame, byte) in out.iter_mut().enumerate() {
+        for channel in 0..8 {
+            if sector[channel * 12 + frame / 8] & (0x80 >> (frame % 8)) != 0 {
+                *byte |= 0x80 >> channel;
+            }
+        }
+    }
+
+    out
+}
+
+/// Converts a stream of 96-byte subcode sectors from one layout to
+/// another, one sector at a time.
+///
+/// The input must end on a sector boundary; a trailing partial sector is
+/// reported as an `InvalidData` error, after every complete sector before
+/// it has been written.
+pub fn transcode<R: Read, W: Write>(
+    mut input: R,
+    from: SubcodeLayout,
+    to: SubcodeLayout,
+    mut output: W,
+) -> io::Result<()> {
+    let mut buf = [0; SUBCODE_SIZE];
+
+    loop {
+        let read = read_full(&mut input, &mut buf)?;
+        if read == 0 {
+            break;
+        }
+        if read != buf.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "incomplete sector; must be exactly {} bytes, was {}",
+                    SUBCODE_SIZE, read
+                ),
+            ));
+        }
+
+        output.write_all(&from.convert(&buf, to))?;
+    }
+
+    Ok(())
+}
+
+impl SubcodeData {
+    /// Parses raw subcode data whose layout isn't known, returning it
+    /// along with the layout it was found to be in. The data is parsed as
+    /// both layouts, and whichever has more sectors with valid Q CRCs is
+    /// chosen. Ties, including data with no valid Q frames at all, are
+    /// treated as deinterleaved.
+    ///
+    /// As with `parse`, the data must be a multiple of 96 bytes.
+    pub fn parse_autodetect(
+        data: Vec<u8>,
+    ) -> Result<(SubcodeData, SubcodeLayout), InvalidDataError> {
+        let mut interleaved = Vec::with_capacity(data.len());
+        for chunk in data.chunks_exact(SUBCODE_SIZE) {
+            let mut sector = [0; SUBCODE_SIZE];
+            sector.copy_from_slice(chunk);
+            interleaved.extend_from_slice(
+                &SubcodeLayout::Interleaved.convert(&sector, SubcodeLayout::Deinterleaved),
+            );
+        }
+
+        let deinterleaved = SubcodeData::parse(data)?;
+        let interleaved = SubcodeData::parse(interleaved)?;
+        let valid = |data: &SubcodeData| {
+            data.channel_iter(SubcodeType::Q)
+                .filter(|q| q.q_crc_valid())
+                .count()
+        };
+
+        if valid(&interleaved) > valid(&deinterleaved) {
+            Ok((interleaved, SubcodeLayout::Interleaved))
+        } else {
+            Ok((deinterleaved, SubcodeLayout::Deinterleaved))
+        }
+    }
+
+    /// Reads a stream of 2448-byte sectors, each containing 2352 bytes of
+    /// user data followed by 96 bytes of subcode, and returns the subcode
+    /// from every sector. The user data is discarded. The subcode must be
+    /// in the `Deinterleaved` layout used by CloneCD; see
+    /// `from_2448_with_layout` for other layouts.
+    ///
+    /// The stream must end on a sector boundary; a trailing partial sector
+    /// is reported as an `InvalidData` error.
+    pub fn from_2448<R: Read>(reader: R) -> io::Result<SubcodeData> {
+        SubcodeData::from_2448_with_layout(reader, SubcodeLayout::Deinterleaved)
+    }
+
+    /// Like `from_2448`, but with the subcode in the given layout. Raw
+    /// reads from most drives return it `Interleaved`.
+    pub fn from_2448_with_layout<R: Read>(
+        mut reader: R,
+        layout: SubcodeLayout,
+    ) -> io::Result<SubcodeData> {
+        let mut sectors = vec![];
+        let mut buf = [0; RAW_SECTOR_WITH_SUBCODE_SIZE];
+
+        loop {
+            let read = read_full(&mut reader, &mut buf)?;
+            if read == 0 {
+                break;
+            }
+            if read != buf.len() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "incomplete sector; must be exactly {} bytes, was {}",
+                        RAW_SECTOR_WITH_SUBCODE_SIZE, read
+                    ),
+                ));
+            }
+
+            let sector = trailing_subcode(&buf, layout)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+            sectors.push(sector);
+        }
+
+        Ok(SubcodeData { sectors })
+    }
+
+    /// Extracts the subcode from the buffer returned by a SCSI READ CD
+    /// (0xBE) command covering `sectors` sectors, with the sync pattern
+    /// left out of the main channel selection and the subcode in the
+    /// `Deinterleaved` layout.
+    ///
+    /// Each sector is 2436 bytes: 2340 bytes of header, user data and
+    /// EDC/ECC, then 96 bytes of subcode. MMC only strips the sync from
+    /// data sectors, so buffers covering audio tracks, whose sectors are
+    /// always 2352 bytes, need `from_readcd_buffer_with_layout` with
+    /// `ReadCdMainChannel::Full`. The main channel data is discarded.
+    /// The buffer must be exactly the size of the requested sectors, or
+    /// `InvalidReadCdBufferLength` is returned.
+    pub fn from_readcd_buffer(
+        data: &[u8],
+        sectors: usize,
+    ) -> Result<SubcodeData, InvalidDataError> {
+        SubcodeData::from_readcd_buffer_with_layout(
+            data,
+            sectors,
+            ReadCdMainChannel::SyncStripped,
+            SubcodeLayout::Deinterleaved,
+        )
+    }
+
+    /// Like `from_readcd_buffer`, but with the given main channel
+    /// selection and subcode layout. Raw P-W sub-channel data (selection
+    /// 001b) is `Interleaved`.
+    ///
+    /// If `sectors` is too large for the buffer size to be represented,
+    /// the error's `expected` length is `usize::MAX`.
+    pub fn from_readcd_buffer_with_layout(
+        data: &[u8],
+        sectors: usize,
+        main_channel: ReadCdMainChannel,
+        layout: SubcodeLayout,
+    ) -> Result<SubcodeData, InvalidDataError> {
+        let sector_size = main_channel.size() + SUBCODE_SIZE;
+        match sectors.checked_mul(sector_size) {
+            Some(expected) if expected == data.len() => {}
+            expected => {
+                return Err(InvalidDataError::InvalidReadCdBufferLength {
+                    expected: expected.unwrap_or(usize::MAX),
+                    length: data.len(),
+                });
+            }
+        }
+
+        let sectors = data
+            .chunks(sector_size)
+            .map(|raw| trailing_subcode(raw, layout))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(SubcodeData { sectors })
+    }
+
+    /// Combines a stream of 2352-byte sectors with this subcode, writing
+    /// 2448-byte sectors to `out` with the subcode in the `Deinterleaved`
+    /// layout. This is the inverse of `from_2448`; see
+    /// `interleave_into_2448_with_layout` for other layouts.
+    ///
+    /// The data stream must contain exactly as many sectors as this
+    /// `SubcodeData`; otherwise an `InvalidInput` error is returned.
+    /// Sectors preceding the mismatch will already have been written.
+    pub fn interleave_into_2448<R: Read, W: Write>(&self, data: R, out: W) -> io::Result<()> {
+        self.interleave_into_2448_with_layout(data, out, SubcodeLayout::Deinterleaved)
+    }
+
+    /// Like `interleave_into_2448`, but writing the subcode in the given
+    /// layout. This is the inverse of `from_2448_with_layout`.
+    pub fn interleave_into_2448_with_layout<R: Read, W: Write>(
+        &self,
+        mut data: R,
+        mut out: W,
+        layout: SubcodeLayout,
+    ) -> io::Result<()> {
+        let mut buf = [0; RAW_SECTOR_SIZE];
+        let mut subcode = [0; SUBCODE_SIZE];
+
+        for (index, sector) in self.sectors.iter().enumerate() {
+            let read = read_full(&mut data, &mut buf)?;
+            if read != buf.len() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "sector count mismatch; data ended at sector {} of {}",
+                        index,
+                        self.sectors.len()
+                    ),
+                ));
+            }
+
+            subcode.copy_from_slice(&sector.to_bytes());
+            out.write_all(&buf)?;
+            out.write_all(&SubcodeLayout::Deinterleaved.convert(&subcode, layout))?;
+        }
+
+        if read_full(&mut data, &mut buf[..1])? != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "sector count mismatch; data has more than {} sectors",
+                    self.sectors.len()
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses the subcode from the last 96 bytes of a raw sector, converting
+/// it from `layout`.
+fn trailing_subcode(raw: &[u8], layout: SubcodeLayout) -> Result<Sector, InvalidDataError> {
+    let mut subcode = [0; SUBCODE_SIZE];
+    subcode.copy_from_slice(&raw[raw.len() - SUBCODE_SIZE..]);
+    Sector::parse(
+        layout
+            .convert(&subcode, SubcodeLayout::Deinterleaved)
+            .to_vec(),
+    )
+}
+
+/// Fills `buf` from `reader`, stopping early only at end of stream.
+/// Returns the number of bytes read.
+pub(crate) fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::msf::Msf;
+    use crate::subcode;
+    use crate::subcode::{transcode, DiscBuilder, ReadCdMainChannel, SubcodeLayout, TrackType};
+    use std::io::Cursor;
+
+    #[test]
+    fn test_from_2448_extracts_trailing_subcode() {
+        let mut data = vec![];
+        // First sector has P and Q set; second sector is full of data
+        data.extend_from_slice(&[0xFF; 2352]);
+        data.extend_from_slice(&[1; 24]);
+        data.extend_from_slice(&[0; 72]);
+        data.extend_from_slice(&[0xFF; 2352]);
+        data.extend_from_slice(&[1; 96]);
+
+        let subcode_data = subcode::SubcodeData::from_2448(Cursor::new(data)).unwrap();
+        assert_eq!(2, subcode_data.sectors.len());
+        assert!(subcode_data.sectors[0].contains_basic_data_only());
+        assert!(!subcode_data.sectors[1].contains_basic_data_only());
+    }
+
+    #[test]
+    fn test_interleave_into_2448_round_trips() {
+        let mut data = vec![];
+        for i in 0..3 {
+            data.extend_from_slice(&[i as u8; 2352]);
+            data.extend_from_slice(&[i as u8 + 1; 96]);
+        }
+
+        let mut user_data = vec![];
+        for sector in data.chunks(2448) {
+            user_data.extend_from_slice(&sector[..2352]);
+        }
+
+        let subcode_data = subcode::SubcodeData::from_2448(Cursor::new(data.clone())).unwrap();
+        let mut out = vec![];
+        subcode_data
+            .interleave_into_2448(Cursor::new(user_data.clone()), &mut out)
+            .unwrap();
+        assert_eq!(data, out);
+
+        for layout in [SubcodeLayout::Deinterleaved, SubcodeLayout::Interleaved] {
+            let subcode_data =
+                subcode::SubcodeData::from_2448_with_layout(Cursor::new(data.clone()), layout)
+                    .unwrap();
+            let mut out = vec![];
+            subcode_data
+                .interleave_into_2448_with_layout(Cursor::new(user_data.clone()), &mut out, layout)
+                .unwrap();
+            assert_eq!(data, out);
+        }
+    }
+
+    #[test]
+    fn test_interleave_into_2448_rejects_mismatched_counts() {
+        let subcode_data = subcode::SubcodeData::parse(vec![0; 192]).unwrap();
+
+        let mut out = vec![];
+        let short = vec![0; 2352];
+        assert!(subcode_data
+            .interleave_into_2448(Cursor::new(short), &mut out)
+            .is_err());
+
+        let mut out = vec![];
+        let long = vec![0; 2352 * 3];
+        assert!(subcode_data
+            .interleave_into_2448(Cursor::new(long), &mut out)
+            .is_err());
+    }
+
+    #[test]
+    fn test_from_2448_rejects_partial_sector() {
+        let data = vec![0; 2448 + 100];
+        assert!(subcode::SubcodeData::from_2448(Cursor::new(data)).is_err());
+    }
+
+    #[test]
+    fn test_from_readcd_buffer() {
+        let disc = DiscBuilder::new()
+            .add_track(1, Msf::new(0, 2, 0), 3, TrackType::Data)
+            .build();
+        // Sync-stripped data sectors, each followed by its subcode
+        let mut buffer = vec![];
+        for sector in &disc.sectors {
+            buffer.extend_from_slice(&[0xAA; 2340]);
+            buffer.extend_from_slice(&sector.to_bytes());
+        }
+
+        let parsed = subcode::SubcodeData::from_readcd_buffer(&buffer, 3).unwrap();
+        assert_eq!(disc.to_bytes(), parsed.to_bytes());
+        assert_eq!(
+            Some(Msf::new(0, 2, 1)),
+            parsed.sectors[1].codes[1].q_absolute()
+        );
+
+        match subcode::SubcodeData::from_readcd_buffer(&buffer, 4) {
+            Err(subcode::InvalidDataError::InvalidReadCdBufferLength { expected, length }) => {
+                assert_eq!(2436 * 4, expected);
+                assert_eq!(2436 * 3, length);
+            }
+            _ => panic!("expected a buffer length error"),
+        }
+        // Too many sectors for the size to be computed
+        match subcode::SubcodeData::from_readcd_buffer(&buffer, usize::MAX) {
+            Err(subcode::InvalidDataError::InvalidReadCdBufferLength { expected, .. }) => {
+                assert_eq!(usize::MAX, expected);
+            }
+            _ => panic!("expected a buffer length error"),
+        }
+    }
+
+    #[test]
+    fn test_from_readcd_buffer_with_layout() {
+        let disc = DiscBuilder::new()
+            .add_track(1, Msf::new(0, 2, 0), 3, TrackType::Audio)
+            .build();
+        let mut buffer = vec![];
+        disc.interleave_into_2448_with_layout(
+            Cursor::new(vec![0xAA; 2352 * 3]),
+            &mut buffer,
+            SubcodeLayout::Interleaved,
+        )
+        .unwrap();
+
+        let parsed = subcode::SubcodeData::from_readcd_buffer_with_layout(
+            &buffer,
+            3,
+            ReadCdMainChannel::Full,
+            SubcodeLayout::Interleaved,
+        )
+        .unwrap();
+        assert_eq!(disc.to_bytes(), parsed.to_bytes());
+        // Reading the same buffer as a stream gives the same subcode
+        let streamed = subcode::SubcodeData::from_2448_with_layout(
+            Cursor::new(buffer.clone()),
+            SubcodeLayout::Interleaved,
+        )
+        .unwrap();
+        assert_eq!(parsed.to_bytes(), streamed.to_bytes());
+
+        // Full sectors don't divide into sync-stripped ones
+        assert!(subcode::SubcodeData::from_readcd_buffer_with_layout(
+            &buffer,
+            3,
+            ReadCdMainChannel::SyncStripped,
+            SubcodeLayout::Interleaved,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_interleaving_places_channels_in_bits() {
+        // P set for every frame, Q empty, W set for the first frame only
+        let mut data = vec![0; 96];
+        data[..12].copy_from_slice(&[0xFF; 12]);
+        data[84] = 0x80;
+
+        let mut out = vec![];
+        transcode(
+            Cursor::new(data),
+            SubcodeLayout::Deinterleaved,
+            SubcodeLayout::Interleaved,
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(0x81, out[0]);
+        assert!(out[1..].iter().all(|b| *b == 0x80));
+    }
+
+    #[test]
+    fn test_transcode_round_trips() {
+        let data: Vec<u8> = (0..96 * 3).map(|i| (i * 131 % 256) as u8).collect();
+
+        let mut interleaved = vec![];
+        transcode(
+            Cursor::new(data.clone()),
+            SubcodeLayout::Deinterleaved,
+            SubcodeLayout::Interleaved,
+            &mut interleaved,
+        )
+        .unwrap();
+        assert_eq!(data.len(), interleaved.len());
+        assert_ne!(data, interleaved);
+
+        let mut deinterleaved = vec![];
+        transcode(
+            Cursor::new(interleaved.clone()),
+            SubcodeLayout::Interleaved,
+            SubcodeLayout::Deinterleaved,
+            &mut deinterleaved,
+        )
+        .unwrap();
+        assert_eq!(data, deinterleaved);
+
+        let mut twice = vec![];
+        transcode(
+            Cursor::new(deinterleaved),
+            SubcodeLayout::Deinterleaved,
+            SubcodeLayout::Interleaved,
+            &mut twice,
+        )
+        .unwrap();
+        assert_eq!(interleaved, twice);
+    }
+
+    #[test]
+    fn test_transcode_rejects_partial_sector() {
+        let mut out = vec![];
+        assert!(transcode(
+            Cursor::new(vec![0; 100]),
+            SubcodeLayout::Interleaved,
+            SubcodeLayout::Deinterleaved,
+            &mut out,
+        )
+        .is_err());
+        assert_eq!(96, out.len());
+    }
+
+    #[test]
+    fn test_parse_autodetect() {
+        let disc = DiscBuilder::new()
+            .add_track(1, Msf::new(0, 2, 0), 20, TrackType::Audio)
+            .build();
+        let deinterleaved = disc.to_bytes();
+        let mut interleaved = vec![];
+        transcode(
+            Cursor::new(deinterleaved.clone()),
+            SubcodeLayout::Deinterleaved,
+            SubcodeLayout::Interleaved,
+            &mut interleaved,
+        )
+        .unwrap();
+
+        let (parsed, layout) = subcode::SubcodeData::parse_autodetect(interleaved).unwrap();
+        assert_eq!(SubcodeLayout::Interleaved, layout);
+        assert_eq!(deinterleaved, parsed.to_bytes());
+
+        let (_, layout) = subcode::SubcodeData::parse_autodetect(deinterleaved).unwrap();
+        assert_eq!(SubcodeLayout::Deinterleaved, layout);
+
+        // Nothing to go on, so the default layout is used
+        let (_, layout) = subcode::SubcodeData::parse_autodetect(vec![0; 192]).unwrap();
+        assert_eq!(SubcodeLayout::Deinterleaved, layout);
+        assert!(subcode::SubcodeData::parse_autodetect(vec![0; 95]).is_err());
+    }
+}
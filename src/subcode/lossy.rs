@@ -0,0 +1,190 @@
+#[cfg(feature = "decode_warnings")]
+use crate::subcode::SubcodeType;
+use crate::subcode::{Sector, SubcodeData};
+
+/// What was wrong with a sector reported by `SubcodeData::parse_lossy`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SectorErrorKind {
+    /// The data ended part way through the sector, which was dropped.
+    /// Holds the number of bytes that were present.
+    Truncated { length: usize },
+    /// The sector was parsed, but doesn't look like real subcode; see
+    /// `Sector::is_plausible`.
+    Implausible,
+}
+
+/// A problem with one sector found by `SubcodeData::parse_lossy`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SectorError {
+    /// The sector's index.
+    pub index: usize,
+    /// Where the sector starts in the original data.
+    pub byte_offset: usize,
+    pub kind: SectorErrorKind,
+}
+
+impl SubcodeData {
+    /// Parses raw subcode data like `parse`, but carries on past problems
+    /// rather than failing, returning a description of each alongside
+    /// whatever could be parsed. Implausible sectors are kept so that
+    /// sector positions stay aligned with the main channel; a trailing
+    /// partial sector is dropped.
+    ///
+    /// With the `decode_warnings` feature, each problem is also logged as
+    /// a warning under the `ccd` target, along with every sector whose Q
+    /// frame fails its CRC check.
+    pub fn parse_lossy(data: &[u8]) -> (SubcodeData, Vec<SectorError>) {
+        let mut sectors = vec![];
+        let mut errors = vec![];
+
+        for (index, chunk) in data.chunks(96).enumerate() {
+            let byte_offset = index * 96;
+            let sector = match Sector::parse(chunk.to_vec()) {
+                Ok(sector) => sector,
+                Err(_) => {
+                    warn!("sector {}: truncated to {} bytes", index, chunk.len());
+                    errors.push(SectorError {
+                        index,
+                        byte_offset,
+                        kind: SectorErrorKind::Truncated {
+                            length: chunk.len(),
+                        },
+                    });
+                    continue;
+                }
+            };
+            #[cfg(feature = "decode_warnings")]
+            {
+                let q = sector.channel(SubcodeType::Q);
+                if !q.is_empty() && !q.q_crc_valid() {
+                    warn!("sector {}: Q CRC mismatch", index);
+                }
+            }
+            if !sector.is_plausible() {
+                warn!("sector {}: implausible subcode", index);
+                errors.push(SectorError {
+                    index,
+                    byte_offset,
+                    kind: SectorErrorKind::Implausible,
+                });
+            }
+            sectors.push(sector);
+        }
+
+        (SubcodeData { sectors }, errors)
+    }
+
+    /// Parses every complete 96-byte sector in `data`, ignoring a trailing
+    /// partial sector such as one left by an interrupted capture. Returns
+    /// the sectors along with the number of bytes ignored, which is 0 if
+    /// `parse` would have accepted the data.
+    pub fn parse_truncating(mut data: Vec<u8>) -> (SubcodeData, usize) {
+        let leftover = data.len() % 96;
+        data.truncate(data.len() - leftover);
+
+        // Unwrapping is safe since the data is now a multiple of 96 bytes
+        (SubcodeData::parse(data).unwrap(), leftover)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "decode_warnings")]
+    use std::cell::RefCell;
+
+    use crate::msf::Msf;
+    use crate::subcode::{DiscBuilder, SectorError, SectorErrorKind, SubcodeData, TrackType};
+
+    #[test]
+    fn test_parse_lossy_reports_byte_offsets() {
+        let mut data = DiscBuilder::new()
+            .add_track(1, Msf::new(0, 2, 0), 5, TrackType::Audio)
+            .build()
+            .to_bytes();
+        // Blank out sector 3, and leave a partial sector at the end
+        for byte in &mut data[288..384] {
+            *byte = 0xFF;
+        }
+        data.extend_from_slice(&[0; 40]);
+
+        let (subcode_data, errors) = SubcodeData::parse_lossy(&data);
+        assert_eq!(5, subcode_data.sectors.len());
+        assert_eq!(
+            vec![
+                SectorError {
+                    index: 3,
+                    byte_offset: 288,
+                    kind: SectorErrorKind::Implausible,
+                },
+                SectorError {
+                    index: 5,
+                    byte_offset: 480,
+                    kind: SectorErrorKind::Truncated { length: 40 },
+                },
+            ],
+            errors
+        );
+    }
+
+    #[test]
+    fn test_parse_truncating() {
+        let mut data = vec![1; 96 * 3];
+        data.extend_from_slice(&[2; 40]);
+        let (subcode_data, leftover) = SubcodeData::parse_truncating(data);
+        assert_eq!(3, subcode_data.sectors.len());
+        assert_eq!(40, leftover);
+        assert_eq!(vec![1; 96 * 3], subcode_data.to_bytes());
+
+        let (subcode_data, leftover) = SubcodeData::parse_truncating(vec![0; 96]);
+        assert_eq!(1, subcode_data.sectors.len());
+        assert_eq!(0, leftover);
+    }
+
+    /// Collects the `ccd` warnings logged on each thread, since the logger
+    /// is shared by every test.
+    #[cfg(feature = "decode_warnings")]
+    struct CapturingLogger;
+
+    #[cfg(feature = "decode_warnings")]
+    thread_local! {
+        static CAPTURED: RefCell<Vec<String>> = const { RefCell::new(vec![]) };
+    }
+
+    #[cfg(feature = "decode_warnings")]
+    impl log::Log for CapturingLogger {
+        fn enabled(&self, metadata: &log::Metadata) -> bool {
+            metadata.target() == "ccd"
+        }
+
+        fn log(&self, record: &log::Record) {
+            if self.enabled(record.metadata()) {
+                CAPTURED.with(|captured| captured.borrow_mut().push(record.args().to_string()));
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    #[cfg(feature = "decode_warnings")]
+    #[test]
+    fn test_parse_lossy_warns_of_crc_mismatches() {
+        static LOGGER: CapturingLogger = CapturingLogger;
+        // Another test may already have installed it
+        let _ = log::set_logger(&LOGGER);
+        log::set_max_level(log::LevelFilter::Warn);
+
+        let mut data = DiscBuilder::new()
+            .add_track(1, Msf::new(0, 2, 0), 5, TrackType::Audio)
+            .build()
+            .to_bytes();
+        // Corrupt the absolute time in sector 2's Q frame
+        data[2 * 96 + 12 + 7] ^= 0xFF;
+        let (_, errors) = SubcodeData::parse_lossy(&data);
+        assert!(errors.is_empty());
+
+        assert_eq!(
+            vec!["sector 2: Q CRC mismatch".to_string()],
+            CAPTURED.with(|captured| captured.borrow_mut().split_off(0))
+        );
+    }
+}
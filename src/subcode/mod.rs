@@ -1,3 +1,34 @@
+use std::convert::TryInto;
+
+mod builder;
+pub(crate) mod channels;
+mod classify;
+#[cfg(feature = "sha2")]
+mod digest;
+mod export;
+mod flags;
+pub(crate) mod layout;
+mod lossy;
+mod offset;
+mod p;
+mod q;
+mod repair;
+mod sessions;
+#[cfg(test)]
+pub(crate) mod testing;
+mod tracks;
+mod verify;
+
+pub use self::builder::DiscBuilder;
+pub use self::channels::ChannelSet;
+pub use self::classify::SectorClass;
+pub use self::flags::{FLAG_ADR_MASK, FLAG_EXTENDED_DATA, FLAG_PAUSE};
+pub use self::layout::{transcode, ReadCdMainChannel, SubcodeLayout};
+pub use self::lossy::{SectorError, SectorErrorKind};
+pub use self::p::BitOrder;
+pub use self::q::{Position, QControl, QCrcConfig, QPosition, QRegion, QSubcode, LEAD_OUT_TRACK};
+pub use self::tracks::{FlagResolution, Track, TrackType, STANDARD_TRACK1_PREGAP};
+
 #[derive(Debug, Fail)]
 pub enum InvalidDataError {
     #[fail(
@@ -17,13 +48,36 @@ pub enum InvalidDataError {
         length
     )]
     InvalidSectorLength { length: usize },
+
+    #[fail(
+        display = "invalid subcode size; must be exactly 12 bytes, was {}",
+        length
+    )]
+    InvalidSubcodeLength { length: usize },
+
+    #[fail(
+        display = "invalid READ CD buffer size; must be exactly {} bytes, was {}",
+        expected, length
+    )]
+    InvalidReadCdBufferLength { expected: usize, length: usize },
 }
 
+#[derive(Clone, Debug, Default)]
 pub struct SubcodeData {
     pub sectors: Vec<Sector>,
 }
 
 impl SubcodeData {
+    /// Creates a `SubcodeData` with no sectors.
+    pub fn new() -> SubcodeData {
+        SubcodeData::default()
+    }
+
+    /// Parses raw subcode data, which must be a multiple of 96 bytes,
+    /// into a `SubcodeData` with one `Sector` per 96 bytes.
+    ///
+    /// Empty input is valid, since zero is a multiple of 96, and
+    /// produces a `SubcodeData` with no sectors.
     pub fn parse(data: Vec<u8>) -> Result<SubcodeData, InvalidDataError> {
         if data.len() % 96 != 0 {
             return Err(InvalidDataError::InvalidSubcodeDataLength { length: data.len() });
@@ -34,7 +88,56 @@ impl SubcodeData {
             sectors.push(Sector::parse(sector.to_vec())?);
         }
 
-        Ok(SubcodeData { sectors: sectors })
+        Ok(SubcodeData { sectors })
+    }
+
+    /// Parses raw subcode data as `parse` does, but into this
+    /// `SubcodeData`, replacing its sectors while reusing their
+    /// allocation. On error, the existing sectors are left untouched.
+    pub fn parse_into(&mut self, data: &[u8]) -> Result<(), InvalidDataError> {
+        if data.len() % 96 != 0 {
+            return Err(InvalidDataError::InvalidSubcodeDataLength { length: data.len() });
+        }
+
+        self.sectors.clear();
+        for sector in data.chunks(96) {
+            self.sectors.push(Sector::parse(sector.to_vec())?);
+        }
+
+        Ok(())
+    }
+
+    /// Returns an iterator over the sectors, in order.
+    pub fn iter(&self) -> std::slice::Iter<'_, Sector> {
+        self.sectors.iter()
+    }
+
+    /// Returns the first sector on the disc, or `None` if it's empty.
+    pub fn first_sector(&self) -> Option<&Sector> {
+        self.sectors.first()
+    }
+
+    /// Returns the last sector on the disc, or `None` if it's empty.
+    pub fn last_sector(&self) -> Option<&Sector> {
+        self.sectors.last()
+    }
+
+    /// Returns the given channel's `Subcode` from every sector, in order.
+    pub fn channel_iter(&self, channel: SubcodeType) -> impl Iterator<Item = &Subcode> {
+        self.sectors
+            .iter()
+            .map(move |sector| sector.channel(channel))
+    }
+
+    /// Serializes every sector back into the 96-byte-per-sector
+    /// layout accepted by `parse`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.sectors.len() * 96);
+        for sector in &self.sectors {
+            bytes.extend_from_slice(&sector.to_bytes());
+        }
+
+        bytes
     }
 
     pub fn contains_basic_data_only(&self) -> bool {
@@ -42,13 +145,92 @@ impl SubcodeData {
             .iter()
             .all(|sector| sector.contains_basic_data_only())
     }
+
+    /// Returns the sectors for editing in place.
+    pub fn sectors_mut(&mut self) -> &mut [Sector] {
+        &mut self.sectors
+    }
+
+    /// Keeps only the sectors for which `f` returns true, in their
+    /// original order. `f` is passed each sector's index before any were
+    /// removed, along with the sector itself, just like `Vec::retain`.
+    pub fn retain_sectors<F: FnMut(usize, &Sector) -> bool>(&mut self, mut f: F) {
+        let mut index = 0;
+        self.sectors.retain(|sector| {
+            let keep = f(index, sector);
+            index += 1;
+            keep
+        });
+    }
+
+    /// Shortens the disc to its first `len` sectors, just like
+    /// `Vec::truncate`. Does nothing if it's already that short.
+    pub fn truncate(&mut self, len: usize) {
+        self.sectors.truncate(len);
+    }
+
+    /// Splits the disc in two at the given sector index, just like
+    /// `slice::split_at`: the first half holds sectors `0..index`, and the
+    /// second the rest.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the number of sectors.
+    pub fn split_at(mut self, index: usize) -> (SubcodeData, SubcodeData) {
+        let second = self.sectors.split_off(index);
+        (self, SubcodeData { sectors: second })
+    }
+}
+
+impl IntoIterator for SubcodeData {
+    type Item = Sector;
+    type IntoIter = std::vec::IntoIter<Sector>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.sectors.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a SubcodeData {
+    type Item = &'a Sector;
+    type IntoIter = std::slice::Iter<'a, Sector>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.sectors.iter()
+    }
 }
 
+/// One sector's worth of subcode: exactly one `Subcode` for each channel,
+/// in P through W order.
+#[derive(Clone, Debug)]
 pub struct Sector {
-    pub codes: Vec<Subcode>,
+    pub codes: [Subcode; 8],
 }
 
 impl Sector {
+    /// Creates a sector whose eight channels all contain zeroes.
+    pub fn empty() -> Sector {
+        // We unwrap here because the data is always exactly 96 bytes
+        Sector::parse(vec![0; 96]).unwrap()
+    }
+
+    /// Assembles a sector from eight subcodes, which must be in P through
+    /// W order and each 12 bytes long.
+    pub fn from_subcodes(codes: [Subcode; 8]) -> Result<Sector, InvalidDataError> {
+        for (index, code) in codes.iter().enumerate() {
+            if code.channel.index() != index {
+                return Err(InvalidDataError::InvalidSubcodeIndex { index });
+            }
+            if code.data.len() != 12 {
+                return Err(InvalidDataError::InvalidSubcodeLength {
+                    length: code.data.len(),
+                });
+            }
+        }
+
+        Ok(Sector { codes })
+    }
+
     /// Parses a 96-byte `Vec` and returns a `Sector` whose data
     /// contains 8 12-byte `Subcode`s.
     pub fn parse(data: Vec<u8>) -> Result<Sector, InvalidDataError> {
@@ -60,11 +242,10 @@ impl Sector {
         }
 
         for (i, data) in data.as_slice().chunks(12).enumerate() {
-            let code;
-            match SubcodeType::from_index(i) {
-                Some(c) => code = c,
+            let code = match SubcodeType::from_index(i) {
+                Some(c) => c,
                 None => return Err(InvalidDataError::InvalidSubcodeIndex { index: i }),
-            }
+            };
             let mut data_vec = vec![];
             data_vec.extend_from_slice(data);
             codes.push(Subcode {
@@ -73,7 +254,51 @@ impl Sector {
             });
         }
 
-        Ok(Sector { codes: codes })
+        // We unwrap here because the length check above guarantees
+        // there are exactly 8 chunks.
+        Ok(Sector {
+            codes: codes.try_into().unwrap(),
+        })
+    }
+
+    /// Returns all eight channels, in the same order as `SubcodeType::from_index`.
+    pub fn channels(&self) -> &[Subcode; 8] {
+        &self.codes
+    }
+
+    /// Returns the `Subcode` for the given channel.
+    pub fn channel(&self, channel: SubcodeType) -> &Subcode {
+        &self.codes[channel.index()]
+    }
+
+    /// Returns the 12 bytes of the given channel.
+    pub fn channel_bytes(&self, channel: SubcodeType) -> &[u8] {
+        &self.channel(channel).data
+    }
+
+    /// Calls `f` with each channel's type and its 12 bytes, in P through W
+    /// order.
+    pub fn visit_channels<F: FnMut(SubcodeType, &[u8])>(&self, mut f: F) {
+        for code in &self.codes {
+            f(code.channel, &code.data);
+        }
+    }
+
+    /// Returns the subcode for the given channel for editing in place.
+    /// Editing the Q channel this way doesn't update its CRC.
+    pub fn channel_mut(&mut self, channel: SubcodeType) -> &mut Subcode {
+        &mut self.codes[channel.index()]
+    }
+
+    /// Serializes the sector back into its 96-byte form, with each
+    /// channel's 12 bytes in P through W order.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(96);
+        for code in &self.codes {
+            bytes.extend_from_slice(&code.data);
+        }
+
+        bytes
     }
 
     /// Checks whether a subcode contains any non-basic subcodes -
@@ -83,10 +308,7 @@ impl Sector {
     pub fn contains_basic_data_only(&self) -> bool {
         self.codes
             .iter()
-            .filter(|code| match code.channel {
-                SubcodeType::P | SubcodeType::Q => false,
-                _ => true,
-            })
+            .filter(|code| !matches!(code.channel, SubcodeType::P | SubcodeType::Q))
             .all(|code| code.is_empty())
     }
 
@@ -110,9 +332,40 @@ impl Sector {
 
         identities
     }
+
+    /// Returns whether the given channel contains no data.
+    pub fn channel_is_empty(&self, channel: SubcodeType) -> bool {
+        self.channel(channel).is_empty()
+    }
+
+    /// Heuristically checks whether this sector looks like real subcode.
+    /// Returns false when all eight channels hold identical bytes, as in
+    /// blank or garbage dumps, or when the Q channel both fails its CRC
+    /// and can't be decoded as any defined frame mode.
+    pub fn is_plausible(&self) -> bool {
+        if self
+            .codes
+            .iter()
+            .all(|code| code.data == self.codes[0].data)
+        {
+            return false;
+        }
+
+        let q = self.channel(SubcodeType::Q);
+        if q.q_crc_valid() {
+            return true;
+        }
+        match q.q_adr() {
+            Some(1) => q.q_position().is_some() && q.q_relative().is_some(),
+            Some(2) => q.q_catalog().is_some(),
+            Some(3) => q.q_isrc().is_some(),
+            Some(5) => true,
+            _ => false,
+        }
+    }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum SubcodeType {
     P,
     Q,
@@ -139,6 +392,35 @@ impl SubcodeType {
         }
     }
 
+    fn index(self) -> usize {
+        match self {
+            SubcodeType::P => 0,
+            SubcodeType::Q => 1,
+            SubcodeType::R => 2,
+            SubcodeType::S => 3,
+            SubcodeType::T => 4,
+            SubcodeType::U => 5,
+            SubcodeType::V => 6,
+            SubcodeType::W => 7,
+        }
+    }
+
+    /// Returns the channel's name along with the role it plays, for use
+    /// in diagnostics.
+    pub fn description(&self) -> &'static str {
+        match *self {
+            SubcodeType::P => "P (pause/gap flag)",
+            SubcodeType::Q => "Q (position, control and TOC)",
+            SubcodeType::R => "R (R-W user data: CD+G, CD-TEXT, CD-MIDI)",
+            SubcodeType::S => "S (R-W user data)",
+            SubcodeType::T => "T (R-W user data)",
+            SubcodeType::U => "U (R-W user data)",
+            SubcodeType::V => "V (R-W user data)",
+            SubcodeType::W => "W (R-W user data)",
+        }
+    }
+
+    #[allow(clippy::inherent_to_string)]
     pub fn to_string(&self) -> String {
         match *self {
             SubcodeType::P => String::from("P"),
@@ -153,12 +435,26 @@ impl SubcodeType {
     }
 }
 
+/// One channel's 12 bytes from a sector. `Subcode::new` checks the
+/// length when constructing one by hand.
+#[derive(Clone, Debug)]
 pub struct Subcode {
     pub channel: SubcodeType,
     pub data: Vec<u8>,
 }
 
 impl Subcode {
+    /// Creates a subcode for `channel`, checking that `data` is exactly
+    /// 12 bytes long. Prefer this over constructing a `Subcode` directly,
+    /// since the decoders assume every channel is 12 bytes.
+    pub fn new(channel: SubcodeType, data: Vec<u8>) -> Result<Subcode, InvalidDataError> {
+        if data.len() != 12 {
+            return Err(InvalidDataError::InvalidSubcodeLength { length: data.len() });
+        }
+
+        Ok(Subcode { channel, data })
+    }
+
     pub fn is_empty(&self) -> bool {
         self.data.iter().all(|byte| byte == &0)
     }
@@ -166,9 +462,12 @@ impl Subcode {
 
 #[cfg(test)]
 mod tests {
+    use crate::msf::Msf;
     use crate::subcode;
+    use crate::subcode::testing;
 
     #[test]
+    #[allow(clippy::nonminimal_bool)]
     fn test_parsing_data_size() {
         let data1 = vec![0; 5];
         assert!(subcode::SubcodeData::parse(data1).is_err());
@@ -177,6 +476,72 @@ mod tests {
         assert!(!subcode::SubcodeData::parse(data2).is_err());
     }
 
+    #[test]
+    fn test_parsing_empty_data() {
+        let subcode_data = subcode::SubcodeData::parse(vec![]).unwrap();
+        assert!(subcode_data.sectors.is_empty());
+    }
+
+    #[test]
+    fn test_parse_into_reuses_subcode_data() {
+        let mut subcode_data = subcode::SubcodeData::parse(vec![1; 96 * 4]).unwrap();
+        let capacity = subcode_data.sectors.capacity();
+
+        subcode_data.parse_into(&[2; 96 * 2]).unwrap();
+        assert_eq!(2, subcode_data.sectors.len());
+        assert_eq!(capacity, subcode_data.sectors.capacity());
+        assert_eq!(vec![2; 96 * 2], subcode_data.to_bytes());
+
+        assert!(subcode_data.parse_into(&[3; 95]).is_err());
+        assert_eq!(vec![2; 96 * 2], subcode_data.to_bytes());
+    }
+
+    #[test]
+    fn test_channel_bytes() {
+        let data: Vec<u8> = (0..96).collect();
+        let sector = subcode::Sector::parse(data).unwrap();
+        let q = sector.channel_bytes(subcode::SubcodeType::Q);
+        assert_eq!(12, q.len());
+        assert_eq!(&(12..24).collect::<Vec<u8>>()[..], q);
+    }
+
+    #[test]
+    fn test_visit_channels() {
+        let data: Vec<u8> = (0..96).collect();
+        let sector = subcode::Sector::parse(data).unwrap();
+        let mut visited = vec![];
+        sector.visit_channels(|channel, bytes| visited.push((channel, bytes[0])));
+
+        use subcode::SubcodeType::*;
+        assert_eq!(
+            vec![
+                (P, 0),
+                (Q, 12),
+                (R, 24),
+                (S, 36),
+                (T, 48),
+                (U, 60),
+                (V, 72),
+                (W, 84)
+            ],
+            visited
+        );
+    }
+
+    #[test]
+    fn test_first_and_last_sector() {
+        let mut data = vec![1; 96];
+        data.extend_from_slice(&[0; 96]);
+        data.extend_from_slice(&[2; 96]);
+        let subcode_data = subcode::SubcodeData::parse(data).unwrap();
+        assert_eq!(vec![1; 96], subcode_data.first_sector().unwrap().to_bytes());
+        assert_eq!(vec![2; 96], subcode_data.last_sector().unwrap().to_bytes());
+
+        let empty = subcode::SubcodeData::new();
+        assert!(empty.first_sector().is_none());
+        assert!(empty.last_sector().is_none());
+    }
+
     #[test]
     fn test_invalid_sector_length() {
         let data = vec![];
@@ -259,6 +624,76 @@ mod tests {
         assert!(!subcode_data.contains_basic_data_only());
     }
 
+    #[test]
+    fn test_editing_through_mutable_accessors() {
+        let mut subcode_data = subcode::SubcodeData::parse(vec![0; 192]).unwrap();
+        subcode_data.sectors_mut()[1]
+            .channel_mut(subcode::SubcodeType::Q)
+            .data[2] = 0x42;
+
+        let bytes = subcode_data.to_bytes();
+        assert_eq!(0x42, bytes[96 + 12 + 2]);
+        assert_eq!(1, bytes.iter().filter(|b| **b != 0).count());
+    }
+
+    #[test]
+    fn test_split_at() {
+        let mut data = vec![];
+        for i in 1..=4 {
+            data.extend_from_slice(&[i; 96]);
+        }
+        let (first, second) = subcode::SubcodeData::parse(data.clone())
+            .unwrap()
+            .split_at(2);
+        assert_eq!(data[..192].to_vec(), first.to_bytes());
+        assert_eq!(data[192..].to_vec(), second.to_bytes());
+
+        let (all, none) = subcode::SubcodeData::parse(data.clone())
+            .unwrap()
+            .split_at(4);
+        assert_eq!(4, all.sectors.len());
+        assert!(none.sectors.is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_split_at_past_end() {
+        subcode::SubcodeData::parse(vec![0; 96 * 4])
+            .unwrap()
+            .split_at(5);
+    }
+
+    #[test]
+    fn test_truncate() {
+        let mut subcode_data = subcode::SubcodeData::parse(vec![0; 96 * 4]).unwrap();
+        subcode_data.truncate(2);
+        assert_eq!(2, subcode_data.sectors.len());
+        subcode_data.truncate(10);
+        assert_eq!(2, subcode_data.sectors.len());
+    }
+
+    #[test]
+    fn test_retain_sectors() {
+        // Sectors 1 and 3 contain extended data
+        let mut data = vec![];
+        for i in 0..4 {
+            data.extend_from_slice(&[0xFF; 24]);
+            data.extend_from_slice(&[(i % 2) as u8; 72]);
+        }
+        let mut subcode_data = subcode::SubcodeData::parse(data).unwrap();
+
+        let mut indices = vec![];
+        subcode_data.retain_sectors(|i, sector| {
+            indices.push(i);
+            !sector.contains_basic_data_only()
+        });
+        assert_eq!(vec![0, 1, 2, 3], indices);
+        assert_eq!(2, subcode_data.sectors.len());
+        assert!(subcode_data
+            .iter()
+            .all(|sector| !sector.contains_basic_data_only()));
+    }
+
     #[test]
     fn test_mixed_sector_modes_reports_correctly() {
         // Disc containing two sectors, one basic and one non-basic
@@ -302,8 +737,159 @@ mod tests {
         assert_eq!(8, sector.contains_data_in_channels().len());
     }
 
+    #[test]
+    fn test_empty_sector() {
+        let sector = subcode::Sector::empty();
+        assert!(sector.contains_basic_data_only());
+        assert!(sector.contains_data_in_channels().is_empty());
+        assert_eq!(subcode::SubcodeType::W, sector.codes[7].channel);
+        assert!(subcode::SubcodeData::new().sectors.is_empty());
+    }
+
+    #[test]
+    fn test_subcode_new_checks_length() {
+        let channel = subcode::SubcodeType::Q;
+        match subcode::Subcode::new(channel, vec![0; 11]) {
+            Err(subcode::InvalidDataError::InvalidSubcodeLength { length }) => {
+                assert_eq!(11, length)
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert!(subcode::Subcode::new(channel, vec![0; 12]).is_ok());
+        assert!(subcode::Subcode::new(channel, vec![0; 13]).is_err());
+    }
+
+    #[test]
+    fn test_sector_from_subcodes() {
+        let sector = subcode::Sector::parse((0..96).collect()).unwrap();
+        let rebuilt = subcode::Sector::from_subcodes(sector.codes.clone()).unwrap();
+        assert_eq!(sector.to_bytes(), rebuilt.to_bytes());
+
+        let mut short = sector.codes.clone();
+        short[3].data.pop();
+        assert!(subcode::Sector::from_subcodes(short).is_err());
+
+        let mut swapped = sector.codes.clone();
+        swapped.swap(0, 1);
+        assert!(subcode::Sector::from_subcodes(swapped).is_err());
+    }
+
+    #[test]
+    fn test_channel_is_empty() {
+        let mut data = vec![1; 24];
+        data.extend_from_slice(&[0; 72]);
+
+        let sector = subcode::Sector::parse(data).unwrap();
+        assert!(!sector.channel_is_empty(subcode::SubcodeType::Q));
+        assert!(sector.channel_is_empty(subcode::SubcodeType::R));
+    }
+
+    #[test]
+    fn test_to_bytes_round_trips() {
+        let mut data = vec![];
+        for i in 0..192 {
+            data.push(i as u8);
+        }
+
+        let subcode_data = subcode::SubcodeData::parse(data.clone()).unwrap();
+        assert_eq!(data, subcode_data.to_bytes());
+    }
+
+    #[test]
+    fn test_identical_channels_are_implausible() {
+        let sector = subcode::Sector::parse(vec![1; 96]).unwrap();
+        assert!(!sector.is_plausible());
+
+        let sector = subcode::Sector::parse(vec![0; 96]).unwrap();
+        assert!(!sector.is_plausible());
+    }
+
+    #[test]
+    fn test_undecodable_q_is_implausible() {
+        let mut data = vec![0xFF; 12];
+        // ADR 7 isn't a defined mode, and the CRC doesn't match
+        data.extend_from_slice(&[0x07, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        data.extend_from_slice(&[0; 72]);
+        let sector = subcode::Sector::parse(data).unwrap();
+        assert!(!sector.is_plausible());
+    }
+
+    #[test]
+    fn test_real_sector_is_plausible() {
+        let sector = testing::sector_with_q(testing::position_frame(
+            0,
+            1,
+            1,
+            Msf::new(0, 0, 0),
+            Msf::new(0, 2, 0),
+        ));
+        assert!(sector.is_plausible());
+
+        // A damaged frame which still decodes is plausible too
+        let mut sector = sector;
+        sector.codes[1].data[10] ^= 0xFF;
+        assert!(sector.is_plausible());
+    }
+
+    #[test]
+    fn test_into_iter_owned() {
+        let subcode_data = subcode::SubcodeData::parse(vec![0; 96 * 3]).unwrap();
+        let mut sectors = vec![];
+        for sector in subcode_data {
+            sectors.push(sector);
+        }
+        assert_eq!(3, sectors.len());
+    }
+
+    #[test]
+    fn test_iter_borrowed() {
+        let subcode_data = subcode::SubcodeData::parse(vec![0; 96 * 3]).unwrap();
+        assert_eq!(3, subcode_data.iter().count());
+        assert_eq!(3, (&subcode_data).into_iter().count());
+    }
+
+    #[test]
+    fn test_channels_are_in_index_order() {
+        let mut data = vec![];
+        for i in 0..8 {
+            data.extend_from_slice(&[i; 12]);
+        }
+        let sector = subcode::Sector::parse(data).unwrap();
+
+        for (i, code) in sector.channels().iter().enumerate() {
+            assert_eq!(subcode::SubcodeType::from_index(i).unwrap(), code.channel);
+            assert_eq!(vec![i as u8; 12], code.data);
+        }
+    }
+
+    #[test]
+    fn test_channel_iter() {
+        let mut data = vec![];
+        for i in 0..3 {
+            data.extend_from_slice(&[0; 12]);
+            data.extend_from_slice(&[i + 1; 12]);
+            data.extend_from_slice(&[0; 72]);
+        }
+        let subcode_data = subcode::SubcodeData::parse(data).unwrap();
+
+        let q: Vec<&subcode::Subcode> =
+            subcode_data.channel_iter(subcode::SubcodeType::Q).collect();
+        assert_eq!(subcode_data.sectors.len(), q.len());
+        for (i, code) in q.iter().enumerate() {
+            assert_eq!(subcode::SubcodeType::Q, code.channel);
+            assert_eq!(vec![i as u8 + 1; 12], code.data);
+        }
+    }
+
     #[test]
     fn test_subcode_type_to_string() {
         assert_eq!("Q", subcode::SubcodeType::Q.to_string());
     }
+
+    #[test]
+    fn test_subcode_type_description() {
+        let description = subcode::SubcodeType::Q.description();
+        assert!(description.starts_with("Q "));
+        assert!(description.contains("position"));
+    }
 }
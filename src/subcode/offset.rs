@@ -0,0 +1,227 @@
+use crate::msf::Msf;
+use crate::subcode::{QRegion, Sector, SubcodeData, SubcodeType};
+
+/// The largest skew, in sectors either way, `detect_pq_skew` looks for.
+const MAX_PQ_SKEW: i32 = 4;
+
+impl SubcodeData {
+    /// Returns a copy of this subcode moved by `offset` sectors, to
+    /// correct for a drive which reads subcode out of step with the
+    /// main channel. A positive offset moves every sector later and a
+    /// negative offset moves them earlier. Sectors moved past either end
+    /// are dropped, and the positions they leave behind are filled with
+    /// empty sectors, so the length is unchanged.
+    pub fn shift(&self, offset: i32) -> SubcodeData {
+        let len = self.sectors.len();
+        let magnitude = (offset.unsigned_abs() as usize).min(len);
+        let mut sectors = Vec::with_capacity(len);
+        if offset >= 0 {
+            sectors.extend((0..magnitude).map(|_| Sector::empty()));
+            sectors.extend_from_slice(&self.sectors[..len - magnitude]);
+        } else {
+            sectors.extend_from_slice(&self.sectors[magnitude..]);
+            sectors.extend((0..magnitude).map(|_| Sector::empty()));
+        }
+
+        SubcodeData { sectors }
+    }
+
+    /// Measures how many sectors the subcode is out of step with the
+    /// main channel, given the absolute time the first sector is known
+    /// to start at. Passing the result to `shift` realigns the subcode.
+    ///
+    /// The time of sector 0 is inferred from the first CRC-valid frame
+    /// with an absolute time, so a damaged first frame doesn't matter.
+    /// Returns 0 if no such frame exists.
+    pub fn detect_offset_against(&self, expected_start: Msf) -> i32 {
+        self.channel_iter(SubcodeType::Q)
+            .enumerate()
+            .filter(|(_, q)| q.q_crc_valid())
+            .find_map(|(i, q)| q.q_absolute().map(|absolute| (i, absolute)))
+            .map(|(i, absolute)| {
+                absolute.frames() as i32 - i as i32 - expected_start.frames() as i32
+            })
+            .unwrap_or(0)
+    }
+
+    /// Estimates how many sectors the P channel is out of step with the
+    /// Q channel, as happens with some capture hardware. The P pause flag
+    /// should be set exactly when Q reports index 00, so each skew of up
+    /// to four sectors either way is tried, and the one where the two
+    /// disagree least often wins. A positive skew means the P channel runs
+    /// late: sector `i`'s pause flag is found in sector `i + skew`.
+    /// Passing the result to `align_pq` corrects it.
+    ///
+    /// Only sectors with a CRC-valid program area frame are compared. Ties
+    /// go to the smallest skew, so a disc without pregaps gives 0.
+    pub fn detect_pq_skew(&self) -> i32 {
+        let indices: Vec<(usize, bool)> = self
+            .channel_iter(SubcodeType::Q)
+            .enumerate()
+            .filter(|(_, q)| q.q_crc_valid() && q.q_region() == Some(QRegion::Program))
+            .filter_map(|(i, q)| q.q_index().map(|index| (i, index == 0)))
+            .collect();
+        // Unwrapping is safe since this is always the P channel
+        let paused = |i: usize| self.sectors[i].channel(SubcodeType::P).p_pause().unwrap();
+
+        let mismatch_rate = |skew: i32| {
+            let (mut compared, mut mismatches) = (0, 0);
+            for (i, pregap) in &indices {
+                let j = *i as i64 + i64::from(skew);
+                if j < 0 || j >= self.sectors.len() as i64 {
+                    continue;
+                }
+                compared += 1;
+                if paused(j as usize) != *pregap {
+                    mismatches += 1;
+                }
+            }
+            if compared == 0 {
+                1.0
+            } else {
+                f64::from(mismatches) / f64::from(compared)
+            }
+        };
+
+        let mut best = (0, mismatch_rate(0));
+        for magnitude in 1..=MAX_PQ_SKEW {
+            for skew in [-magnitude, magnitude] {
+                let rate = mismatch_rate(skew);
+                if rate < best.1 {
+                    best = (skew, rate);
+                }
+            }
+        }
+
+        best.0
+    }
+
+    /// Moves the P channel by `skew` sectors to line it up with the Q
+    /// channel, as measured by `detect_pq_skew`: sector `i` takes its P
+    /// channel from sector `i + skew`. The other channels are untouched.
+    /// Sectors left without a P channel get an empty one.
+    pub fn align_pq(&mut self, skew: i32) {
+        let p: Vec<Vec<u8>> = self
+            .channel_iter(SubcodeType::P)
+            .map(|p| p.data.clone())
+            .collect();
+        for (i, sector) in self.sectors.iter_mut().enumerate() {
+            let j = i as i64 + i64::from(skew);
+            sector.channel_mut(SubcodeType::P).data = if j >= 0 && j < p.len() as i64 {
+                p[j as usize].clone()
+            } else {
+                vec![0; 12]
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::msf::Msf;
+    use crate::subcode;
+    use crate::subcode::{DiscBuilder, TrackType};
+
+    fn numbered_disc() -> subcode::SubcodeData {
+        let mut data = vec![];
+        for i in 1..=4 {
+            data.extend_from_slice(&[i; 96]);
+        }
+        subcode::SubcodeData::parse(data).unwrap()
+    }
+
+    fn first_bytes(subcode_data: &subcode::SubcodeData) -> Vec<u8> {
+        subcode_data
+            .sectors
+            .iter()
+            .map(|sector| sector.codes[0].data[0])
+            .collect()
+    }
+
+    #[test]
+    fn test_positive_shift() {
+        let shifted = numbered_disc().shift(1);
+        assert_eq!(4, shifted.sectors.len());
+        assert_eq!(vec![0, 1, 2, 3], first_bytes(&shifted));
+    }
+
+    #[test]
+    fn test_negative_shift() {
+        let shifted = numbered_disc().shift(-2);
+        assert_eq!(4, shifted.sectors.len());
+        assert_eq!(vec![3, 4, 0, 0], first_bytes(&shifted));
+    }
+
+    #[test]
+    fn test_shift_beyond_length() {
+        let shifted = numbered_disc().shift(10);
+        assert_eq!(4, shifted.sectors.len());
+        assert_eq!(vec![0, 0, 0, 0], first_bytes(&shifted));
+    }
+
+    fn one_track_disc(start: Msf) -> subcode::SubcodeData {
+        DiscBuilder::new()
+            .start(start)
+            .append_track(1, 0, 20, TrackType::Audio)
+            .build()
+    }
+
+    #[test]
+    fn test_detect_offset_against() {
+        let disc = one_track_disc(Msf::new(0, 2, 5));
+        let offset = disc.detect_offset_against(Msf::new(0, 2, 0));
+        assert_eq!(5, offset);
+
+        // Shifting by the detected offset puts 00:02:05 where it belongs
+        let aligned = disc.shift(offset);
+        assert_eq!(
+            Some(Msf::new(0, 2, 5)),
+            aligned.sectors[5].codes[1].q_absolute()
+        );
+    }
+
+    #[test]
+    fn test_detect_offset_skips_bad_frames() {
+        let mut disc = one_track_disc(Msf::new(0, 1, 74));
+        disc.sectors[0].codes[1].data[9] ^= 0xFF;
+        assert_eq!(-1, disc.detect_offset_against(Msf::new(0, 2, 0)));
+    }
+
+    #[test]
+    fn test_detect_offset_without_q() {
+        assert_eq!(0, numbered_disc().detect_offset_against(Msf::new(0, 2, 0)));
+    }
+
+    fn pregap_disc() -> subcode::SubcodeData {
+        DiscBuilder::new()
+            .append_track(1, 0, 10, TrackType::Audio)
+            .append_track(2, 4, 10, TrackType::Audio)
+            .build()
+    }
+
+    #[test]
+    fn test_detect_pq_skew() {
+        let original = pregap_disc();
+        assert_eq!(0, original.detect_pq_skew());
+
+        // The P channel arrives a sector late
+        let mut skewed = pregap_disc();
+        skewed.align_pq(-1);
+        assert_eq!(1, skewed.detect_pq_skew());
+        assert!(!skewed.verify_p_q_consistency().is_empty());
+
+        skewed.align_pq(1);
+        assert_eq!(0, skewed.detect_pq_skew());
+        assert!(skewed.verify_p_q_consistency().is_empty());
+        assert_eq!(
+            original.sectors[..23]
+                .iter()
+                .map(|s| &s.codes[0].data)
+                .collect::<Vec<_>>(),
+            skewed.sectors[..23]
+                .iter()
+                .map(|s| &s.codes[0].data)
+                .collect::<Vec<_>>()
+        );
+    }
+}
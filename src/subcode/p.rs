@@ -0,0 +1,223 @@
+use crate::subcode::{Sector, Subcode, SubcodeData, SubcodeType};
+
+/// The order in which each byte of a channel holds its eight frames.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum BitOrder {
+    /// The first frame is in the most significant bit. This is the usual
+    /// convention, and the one the rest of this crate assumes.
+    #[default]
+    MsbFirst,
+    /// The first frame is in the least significant bit.
+    LsbFirst,
+}
+
+impl Sector {
+    /// Returns the bit of `channel` for the given frame, 0 through 95,
+    /// reading each byte in the given order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frame` is 96 or more.
+    pub fn bit(&self, channel: SubcodeType, frame: usize, order: BitOrder) -> bool {
+        let byte = self.channel(channel).data[frame / 8];
+        let mask = match order {
+            BitOrder::MsbFirst => 0x80 >> (frame % 8),
+            BitOrder::LsbFirst => 1 << (frame % 8),
+        };
+        byte & mask != 0
+    }
+}
+
+impl Subcode {
+    /// Returns whether a P channel subcode signals a pause, which is the
+    /// case during pregaps and the lead-out. Since the flag is repeated
+    /// in every bit, a sector counts as paused when most of its bits are
+    /// set, tolerating a few read errors. Returns `None` for any other
+    /// channel.
+    pub fn p_pause(&self) -> Option<bool> {
+        match self.channel {
+            SubcodeType::P => {
+                let set: u32 = self.data.iter().map(|byte| byte.count_ones()).sum();
+                Some(set as usize * 2 > self.data.len() * 8)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl SubcodeData {
+    /// Returns every bit of the P channel across the disc, most
+    /// significant bit first, 96 bits per sector. This allows a pause's
+    /// start or end to be located to the exact frame rather than to the
+    /// sector.
+    pub fn p_bits(&self) -> Vec<bool> {
+        self.channel_iter(SubcodeType::P)
+            .flat_map(|p| p.data.iter())
+            .flat_map(|byte| (0..8).map(move |bit| byte & (0x80 >> bit) != 0))
+            .collect()
+    }
+
+    /// Works out the order in which the dump stores each byte's frames,
+    /// from where the P channel's pause flag switches on or off. Since the
+    /// flag only changes at the start or end of a pause, every byte on
+    /// either side of a change is 0x00 or 0xFF, and the byte it happens in
+    /// shows which end of the byte comes first: a pause starting part way
+    /// through a byte sets its low bits if the most significant bit comes
+    /// first, and its high bits otherwise.
+    ///
+    /// Returns `BitOrder::MsbFirst` if there are no such changes, or if
+    /// they don't favour either order.
+    pub fn detect_bit_order(&self) -> BitOrder {
+        let bytes: Vec<u8> = self
+            .channel_iter(SubcodeType::P)
+            .flat_map(|p| p.data.iter().cloned())
+            .collect();
+        // Whether a byte's set bits are all at its low end, or its high end
+        let low = |byte: u8| byte & byte.wrapping_add(1) == 0;
+        let high = |byte: u8| low(!byte);
+
+        let mut msb = 0;
+        let mut lsb = 0;
+        for window in bytes.windows(3) {
+            let (before, byte, after) = (window[0], window[1], window[2]);
+            if byte == 0x00 || byte == 0xFF {
+                continue;
+            }
+            let (msb_vote, lsb_vote) = match (before, after) {
+                (0x00, 0xFF) => (low(byte), high(byte)),
+                (0xFF, 0x00) => (high(byte), low(byte)),
+                _ => continue,
+            };
+            msb += msb_vote as usize;
+            lsb += lsb_vote as usize;
+        }
+
+        if lsb > msb {
+            BitOrder::LsbFirst
+        } else {
+            BitOrder::MsbFirst
+        }
+    }
+
+    /// Locates a track's pregap to the exact frame, as a pair of
+    /// positions into `p_bits`: the first frame of the pause and one past
+    /// its last frame. Divide by 96 to get the sector, and take the
+    /// remainder to get the frame within it.
+    ///
+    /// The Q channel gives the pregap to the nearest sector; each end is
+    /// then refined by looking for the P channel changing within a sector
+    /// of it. An end where no change is found is left on the sector
+    /// boundary. Returns `None` if the track or its pregap isn't present.
+    pub fn pregap_frames(&self, track: u8) -> Option<(usize, usize)> {
+        let track = self.tracks().into_iter().find(|t| t.number == track)?;
+        let pregap_start = track.pregap_start?;
+        let bits = self.p_bits();
+
+        let edge = |sector: usize, rising: bool| {
+            let window = sector.saturating_sub(1) * 96..((sector + 1) * 96).min(bits.len());
+            window
+                .filter(|&bit| bit > 0)
+                .find(|&bit| bits[bit] == rising && bits[bit - 1] != rising)
+                .unwrap_or(sector * 96)
+        };
+
+        Some((edge(pregap_start, true), edge(track.start, false)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::subcode;
+    use crate::subcode::{DiscBuilder, TrackType};
+
+    #[test]
+    fn test_p_pause() {
+        let mut subcode = subcode::Subcode {
+            channel: subcode::SubcodeType::P,
+            data: vec![0xFF; 12],
+        };
+        assert_eq!(Some(true), subcode.p_pause());
+
+        // A couple of flipped bits don't change the result
+        subcode.data[3] = 0xFC;
+        assert_eq!(Some(true), subcode.p_pause());
+
+        subcode.data = vec![0; 12];
+        assert_eq!(Some(false), subcode.p_pause());
+    }
+
+    #[test]
+    fn test_p_pause_on_other_channels() {
+        let subcode = subcode::Subcode {
+            channel: subcode::SubcodeType::Q,
+            data: vec![0xFF; 12],
+        };
+        assert_eq!(None, subcode.p_pause());
+    }
+
+    #[test]
+    fn test_p_bits_finds_transition_mid_sector() {
+        let mut data = vec![0; 96];
+        // The pause begins part way through the second byte of sector 2
+        let mut second = vec![0; 12];
+        second[1] = 0x0F;
+        for byte in second.iter_mut().skip(2) {
+            *byte = 0xFF;
+        }
+        data.extend_from_slice(&second);
+        data.extend_from_slice(&[0; 84]);
+
+        let bits = subcode::SubcodeData::parse(data).unwrap().p_bits();
+        assert_eq!(192, bits.len());
+        assert_eq!(Some(96 + 12), bits.iter().position(|bit| *bit));
+        assert!(bits[108..].iter().all(|bit| *bit));
+    }
+
+    #[test]
+    fn test_pregap_frames() {
+        let mut disc = DiscBuilder::new()
+            .append_track(1, 0, 10, TrackType::Audio)
+            .append_track(2, 3, 5, TrackType::Audio)
+            .build();
+        // The pause begins at bit 40 of the last sector of track 1
+        disc.sectors[9].codes[0].data =
+            vec![0, 0, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF];
+
+        assert_eq!(Some((9 * 96 + 40, 13 * 96)), disc.pregap_frames(2));
+        assert_eq!(None, disc.pregap_frames(1));
+        assert_eq!(None, disc.pregap_frames(3));
+    }
+
+    /// Two sectors whose P channel pause starts at frame 20 of the second
+    /// and ends at frame 60 of it, stored with the given transition bytes.
+    fn p_transitions(rising: u8, falling: u8) -> subcode::SubcodeData {
+        let mut data = vec![0; 96];
+        let mut p = vec![0, 0, rising, 0xFF, 0xFF, 0xFF, 0xFF, falling, 0, 0, 0, 0];
+        p.extend_from_slice(&[0; 84]);
+        data.extend_from_slice(&p);
+        subcode::SubcodeData::parse(data).unwrap()
+    }
+
+    #[test]
+    fn test_detect_bit_order() {
+        use crate::subcode::{BitOrder, SubcodeType};
+
+        let msb = p_transitions(0x0F, 0xF0);
+        let lsb = p_transitions(0xF0, 0x0F);
+        assert_eq!(BitOrder::MsbFirst, msb.detect_bit_order());
+        assert_eq!(BitOrder::LsbFirst, lsb.detect_bit_order());
+        assert_eq!(
+            BitOrder::MsbFirst,
+            subcode::SubcodeData::new().detect_bit_order()
+        );
+
+        // Read in their own order, both pauses cover frames 20 to 59
+        for (disc, order) in &[(msb, BitOrder::MsbFirst), (lsb, BitOrder::LsbFirst)] {
+            let sector = &disc.sectors[1];
+            let set: Vec<usize> = (0..96)
+                .filter(|frame| sector.bit(SubcodeType::P, *frame, *order))
+                .collect();
+            assert_eq!((20..60).collect::<Vec<_>>(), set);
+        }
+    }
+}
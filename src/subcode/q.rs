@@ -0,0 +1,794 @@
+use std::fmt;
+
+use crate::bcd::{self, to_bcd};
+use crate::crc;
+use crate::msf::Msf;
+use crate::subcode::{Sector, Subcode, SubcodeData, SubcodeType};
+
+/// Track number used by the Q channel in the lead-out area.
+pub const LEAD_OUT_TRACK: u8 = 0xAA;
+
+/// How the CRC is stored in the last two bytes of a Q frame. The Red
+/// Book stores it inverted with the high byte first, which is the
+/// default, but some drives return it differently.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QCrcConfig {
+    /// The stored CRC is the complement of the computed one.
+    pub invert: bool,
+    /// The stored CRC has its low byte first.
+    pub swap: bool,
+}
+
+impl Default for QCrcConfig {
+    fn default() -> QCrcConfig {
+        QCrcConfig {
+            invert: true,
+            swap: false,
+        }
+    }
+}
+
+/// The four control bits carried in the high nibble of every Q frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "bincode", derive(serde::Serialize, serde::Deserialize))]
+pub struct QControl {
+    /// The track contains four-channel rather than two-channel audio.
+    pub four_channel: bool,
+    /// The track contains data rather than audio.
+    pub data: bool,
+    /// Digital copying of the track is permitted.
+    pub copy_permitted: bool,
+    /// The audio was mastered with pre-emphasis. For data tracks this
+    /// bit instead marks the track as recorded incrementally.
+    pub pre_emphasis: bool,
+}
+
+impl QControl {
+    /// Decodes the control bits from the low four bits of `nibble`.
+    pub fn from_nibble(nibble: u8) -> QControl {
+        QControl {
+            four_channel: nibble & 0b1000 != 0,
+            data: nibble & 0b0100 != 0,
+            copy_permitted: nibble & 0b0010 != 0,
+            pre_emphasis: nibble & 0b0001 != 0,
+        }
+    }
+
+    /// Encodes the control bits back into a nibble.
+    pub fn to_nibble(self) -> u8 {
+        (self.four_channel as u8) << 3
+            | (self.data as u8) << 2
+            | (self.copy_permitted as u8) << 1
+            | self.pre_emphasis as u8
+    }
+}
+
+/// The area of the disc an ADR-1 Q frame describes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum QRegion {
+    LeadIn,
+    Program,
+    LeadOut,
+}
+
+/// What the track number and index bytes of an ADR-1 frame identify.
+/// In the lead-in, the index byte holds a TOC POINT instead, which must
+/// not be mistaken for a track or index number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum QPosition {
+    /// A lead-in frame carrying the TOC entry for the given POINT, kept
+    /// as its raw byte since POINTs such as 0xA0 aren't BCD.
+    LeadIn { point: u8 },
+    /// A program area frame within the given track and index.
+    Program { track: u8, index: u8 },
+    /// A lead-out frame.
+    LeadOut,
+}
+
+/// The position carried by a CRC-valid ADR-1 frame in the program area
+/// or lead-out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Position {
+    pub control: QControl,
+    /// The track number, 1 through 99, or `LEAD_OUT_TRACK`.
+    pub track: u8,
+    pub index: u8,
+    pub relative: Msf,
+    pub absolute: Msf,
+}
+
+/// Every field of a Q frame, decoded at once.
+///
+/// The positional fields are only present for ADR-1 frames, and are
+/// `None` for other modes or if their BCD is invalid. As with the
+/// individual decoders, `absolute` is also `None` in the lead-in.
+#[derive(Clone, Debug, PartialEq)]
+pub struct QSubcode {
+    pub control: QControl,
+    pub adr: u8,
+    pub region: Option<QRegion>,
+    pub track: Option<u8>,
+    pub index: Option<u8>,
+    pub relative: Option<Msf>,
+    pub absolute: Option<Msf>,
+    pub crc_valid: bool,
+}
+
+impl Sector {
+    /// Decodes this sector's Q channel. Returns `None` if the Q channel
+    /// is empty.
+    pub fn q(&self) -> Option<QSubcode> {
+        let q = self.channel(SubcodeType::Q);
+        if q.is_empty() {
+            return None;
+        }
+
+        // Unwrapping is safe since we know this is the Q channel
+        Some(QSubcode {
+            control: q.q_control().unwrap(),
+            adr: q.q_adr().unwrap(),
+            region: q.q_region(),
+            track: q.q_track(),
+            index: q.q_index(),
+            relative: q.q_relative(),
+            absolute: q.q_absolute(),
+            crc_valid: q.q_crc_valid(),
+        })
+    }
+}
+
+impl SubcodeData {
+    /// Decodes the Q channel of every sector whose Q channel is present
+    /// and passes its CRC, paired with the sector's index. Sectors are
+    /// decoded as the iterator is advanced.
+    pub fn valid_q_frames(&self) -> impl Iterator<Item = (usize, QSubcode)> + '_ {
+        self.sectors
+            .iter()
+            .enumerate()
+            .filter_map(|(i, sector)| sector.q().filter(|q| q.crc_valid).map(|q| (i, q)))
+    }
+
+    /// Decodes the position of every sector with a CRC-valid ADR-1 frame
+    /// in the program area or lead-out, in sector order. Other sectors,
+    /// including the lead-in, are skipped.
+    pub fn positions(&self) -> impl Iterator<Item = Position> + '_ {
+        self.valid_q_frames().filter_map(|(_, q)| {
+            if q.adr != 1 || q.region == Some(QRegion::LeadIn) {
+                return None;
+            }
+            Some(Position {
+                control: q.control,
+                track: q.track?,
+                index: q.index?,
+                relative: q.relative?,
+                absolute: q.absolute?,
+            })
+        })
+    }
+
+    /// Builds a disc with one sector for each position, in order. Each
+    /// sector has an ADR-1 Q frame with a valid CRC, and every other
+    /// channel is left empty. This is the inverse of `positions`.
+    pub fn from_positions(positions: &[Position]) -> SubcodeData {
+        let sectors = positions
+            .iter()
+            .map(|position| {
+                let mut sector = Sector::empty();
+                sector.channel_mut(SubcodeType::Q).data = position_frame(
+                    position.control.to_nibble(),
+                    position.track,
+                    position.index,
+                    position.relative,
+                    position.absolute,
+                );
+                sector
+            })
+            .collect();
+
+        SubcodeData { sectors }
+    }
+}
+
+/// Summarises the sector's Q channel on one line, for example
+/// `[01/01 rel 00:00:05 abs 00:02:05 AUDIO crc:ok]`. Fields which can't be
+/// decoded are shown as dashes, and frames other than ADR-1 show their
+/// ADR in place of the position. An empty Q channel is shown as `[no Q]`.
+impl fmt::Display for Sector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let q = match self.q() {
+            Some(q) => q,
+            None => return f.write_str("[no Q]"),
+        };
+        let kind = if q.control.data { "DATA" } else { "AUDIO" };
+        let crc = if q.crc_valid { "ok" } else { "bad" };
+
+        if q.adr != 1 {
+            return write!(f, "[ADR {} {} crc:{}]", q.adr, kind, crc);
+        }
+
+        let number = |number: Option<u8>| match number {
+            Some(LEAD_OUT_TRACK) => "AA".to_string(),
+            Some(number) => format!("{:02}", number),
+            None => "--".to_string(),
+        };
+        let time = |time: Option<Msf>| match time {
+            Some(time) => time.to_string(),
+            None => "--:--:--".to_string(),
+        };
+        write!(
+            f,
+            "[{}/{} rel {} abs {} {} crc:{}]",
+            number(q.track),
+            number(q.index),
+            time(q.relative),
+            time(q.absolute),
+            kind,
+            crc
+        )
+    }
+}
+
+impl Subcode {
+    /// Returns the raw first byte of a Q channel subcode, holding the
+    /// control bits in its high nibble and the ADR in its low nibble.
+    /// Returns `None` for any other channel.
+    pub fn q_adr_control_byte(&self) -> Option<u8> {
+        self.q_byte(0)
+    }
+
+    /// Returns the ADR (mode) nibble of a Q channel subcode, which
+    /// determines how the rest of the frame is laid out.
+    /// Returns `None` for any other channel.
+    pub fn q_adr(&self) -> Option<u8> {
+        self.q_adr_control_byte().map(|byte| byte & 0x0F)
+    }
+
+    /// Returns the control bits of a Q channel subcode.
+    /// Returns `None` for any other channel.
+    pub fn q_control(&self) -> Option<QControl> {
+        self.q_adr_control_byte()
+            .map(|byte| QControl::from_nibble(byte >> 4))
+    }
+
+    /// Returns the CRC stored in the last two bytes of a Q channel subcode,
+    /// un-inverted so that it can be compared with a computed CRC.
+    /// Returns `None` for any other channel.
+    pub fn q_crc(&self) -> Option<u16> {
+        let high = self.q_byte(10)?;
+        let low = self.q_byte(11)?;
+        Some(!(u16::from(high) << 8 | u16::from(low)))
+    }
+
+    /// Checks whether the stored CRC of a Q channel subcode matches its
+    /// contents. Always false for any other channel.
+    pub fn q_crc_valid(&self) -> bool {
+        self.q_crc_valid_with(QCrcConfig::default())
+    }
+
+    /// Like `q_crc_valid`, but reads the stored CRC using the given
+    /// convention rather than the Red Book's.
+    pub fn q_crc_valid_with(&self, config: QCrcConfig) -> bool {
+        let (high, low) = match (self.q_byte(10), self.q_byte(11)) {
+            (Some(high), Some(low)) => (high, low),
+            _ => return false,
+        };
+        let (high, low) = if config.swap {
+            (low, high)
+        } else {
+            (high, low)
+        };
+        let mut stored = u16::from(high) << 8 | u16::from(low);
+        if config.invert {
+            stored = !stored;
+        }
+
+        stored == crc::crc16(&self.data[..10])
+    }
+
+    /// Returns which area of the disc an ADR-1 frame belongs to, based on
+    /// its track number. Returns `None` for other frame modes.
+    pub fn q_region(&self) -> Option<QRegion> {
+        match self.position_byte(1)? {
+            0x00 => Some(QRegion::LeadIn),
+            LEAD_OUT_TRACK => Some(QRegion::LeadOut),
+            _ => Some(QRegion::Program),
+        }
+    }
+
+    /// Returns the track number of an ADR-1 frame: 0 in the lead-in,
+    /// `LEAD_OUT_TRACK` in the lead-out, and otherwise 1 through 99.
+    /// Returns `None` for other frame modes or invalid BCD.
+    pub fn q_track(&self) -> Option<u8> {
+        match self.position_byte(1)? {
+            LEAD_OUT_TRACK => Some(LEAD_OUT_TRACK),
+            byte => bcd::from_bcd(byte),
+        }
+    }
+
+    /// Returns the index number of an ADR-1 frame, where index 00 is
+    /// the pregap and index 01 onward is the track itself.
+    /// Returns `None` for other frame modes, for lead-in frames, where
+    /// this byte holds a POINT instead, or for invalid BCD.
+    pub fn q_index(&self) -> Option<u8> {
+        if self.q_region()? == QRegion::LeadIn {
+            return None;
+        }
+        bcd::from_bcd(self.position_byte(2)?)
+    }
+
+    /// Returns the POINT of a lead-in ADR-1 frame, which identifies the
+    /// TOC entry it carries. Returns `None` for other frame modes and
+    /// outside the lead-in.
+    pub fn q_point(&self) -> Option<u8> {
+        match self.q_region()? {
+            QRegion::LeadIn => self.position_byte(2),
+            _ => None,
+        }
+    }
+
+    /// Decodes the track number and index bytes of an ADR-1 frame
+    /// according to its region. Returns `None` for other frame modes or
+    /// if a program area frame's track or index isn't valid BCD.
+    pub fn q_position(&self) -> Option<QPosition> {
+        match self.q_region()? {
+            QRegion::LeadIn => Some(QPosition::LeadIn {
+                point: self.q_point()?,
+            }),
+            QRegion::Program => Some(QPosition::Program {
+                track: self.q_track()?,
+                index: self.q_index()?,
+            }),
+            QRegion::LeadOut => Some(QPosition::LeadOut),
+        }
+    }
+
+    /// Returns the time relative to the start of the current track of an
+    /// ADR-1 frame. This counts down through a pregap and up again from
+    /// index 01. Returns `None` for other frame modes or invalid BCD.
+    pub fn q_relative(&self) -> Option<Msf> {
+        Msf::from_bcd(
+            self.position_byte(3)?,
+            self.position_byte(4)?,
+            self.position_byte(5)?,
+        )
+    }
+
+    /// Returns the absolute time on the disc of an ADR-1 frame.
+    /// Returns `None` for other frame modes, for invalid BCD, or in the
+    /// lead-in, where these bytes describe a TOC entry instead.
+    pub fn q_absolute(&self) -> Option<Msf> {
+        if self.q_region()? == QRegion::LeadIn {
+            return None;
+        }
+
+        Msf::from_bcd(
+            self.position_byte(7)?,
+            self.position_byte(8)?,
+            self.position_byte(9)?,
+        )
+    }
+
+    /// Returns the frame counter carried by ADR-2 (catalog number) and
+    /// ADR-3 (ISRC) frames. These frames don't carry a full position,
+    /// only the absolute frame number stored in their last data byte,
+    /// which lets readers follow how they're spread across sectors.
+    /// Returns `None` for other frame modes or if the counter isn't
+    /// valid BCD.
+    pub fn q_frame_counter(&self) -> Option<u8> {
+        match self.q_adr() {
+            Some(2) | Some(3) => self.q_byte(9).and_then(bcd::from_bcd),
+            _ => None,
+        }
+    }
+
+    /// Returns the 13-digit media catalog number (MCN) from an ADR-2 frame.
+    /// Returns `None` for other frame modes or if any digit is invalid.
+    pub fn q_catalog(&self) -> Option<String> {
+        if self.q_adr()? != 2 {
+            return None;
+        }
+
+        let mut catalog = String::with_capacity(13);
+        for i in 0..13 {
+            let byte = self.data[1 + i / 2];
+            let digit = if i % 2 == 0 { byte >> 4 } else { byte & 0x0F };
+            if digit > 9 {
+                return None;
+            }
+            catalog.push((b'0' + digit) as char);
+        }
+
+        Some(catalog)
+    }
+
+    /// Returns the 12-character ISRC from an ADR-3 frame. The first five
+    /// characters are stored as 6-bit values and the last seven as BCD.
+    /// Returns `None` for other frame modes or if any character is invalid.
+    pub fn q_isrc(&self) -> Option<String> {
+        if self.q_adr()? != 3 {
+            return None;
+        }
+
+        let mut isrc = String::with_capacity(12);
+        let packed = u32::from(self.data[1]) << 24
+            | u32::from(self.data[2]) << 16
+            | u32::from(self.data[3]) << 8
+            | u32::from(self.data[4]);
+        for i in 0..5 {
+            let value = ((packed >> (26 - i * 6)) & 0x3F) as u8;
+            match value {
+                0x00..=0x09 | 0x11..=0x2A => isrc.push((b'0' + value) as char),
+                _ => return None,
+            }
+        }
+        for i in 0..7 {
+            let byte = self.data[5 + i / 2];
+            let digit = if i % 2 == 0 { byte >> 4 } else { byte & 0x0F };
+            if digit > 9 {
+                return None;
+            }
+            isrc.push((b'0' + digit) as char);
+        }
+
+        Some(isrc)
+    }
+
+    fn q_byte(&self, index: usize) -> Option<u8> {
+        match self.channel {
+            SubcodeType::Q => self.data.get(index).cloned(),
+            _ => None,
+        }
+    }
+
+    /// Returns a byte from an ADR-1 (position) frame.
+    fn position_byte(&self, index: usize) -> Option<u8> {
+        match self.q_adr()? {
+            1 => self.q_byte(index),
+            _ => None,
+        }
+    }
+}
+
+/// Stores the inverted CRC of the first ten bytes of a Q frame.
+pub(crate) fn with_crc(mut frame: Vec<u8>) -> Vec<u8> {
+    let crc = !crc::crc16(&frame[..10]);
+    frame[10] = (crc >> 8) as u8;
+    frame[11] = crc as u8;
+    frame
+}
+
+/// Builds an ADR-1 position frame with a valid CRC.
+pub(crate) fn position_frame(
+    control: u8,
+    track: u8,
+    index: u8,
+    relative: Msf,
+    absolute: Msf,
+) -> Vec<u8> {
+    let track = if track == LEAD_OUT_TRACK {
+        LEAD_OUT_TRACK
+    } else {
+        to_bcd(track)
+    };
+    with_crc(vec![
+        control << 4 | 1,
+        track,
+        to_bcd(index),
+        to_bcd(relative.minute),
+        to_bcd(relative.second),
+        to_bcd(relative.frame),
+        0,
+        to_bcd(absolute.minute),
+        to_bcd(absolute.second),
+        to_bcd(absolute.frame),
+        0,
+        0,
+    ])
+}
+
+/// Builds an ADR-2 catalog number frame with a valid CRC. The catalog
+/// number should be 13 digits; other characters won't decode.
+pub(crate) fn catalog_frame(catalog: &str, frame: u8) -> Vec<u8> {
+    let mut data = vec![0x02, 0, 0, 0, 0, 0, 0, 0, 0, to_bcd(frame), 0, 0];
+    for (i, digit) in catalog.bytes().enumerate() {
+        let digit = digit.wrapping_sub(b'0') & 0x0F;
+        data[1 + i / 2] |= if i % 2 == 0 { digit << 4 } else { digit };
+    }
+    with_crc(data)
+}
+
+/// Builds an ADR-3 ISRC frame with a valid CRC. The ISRC should be five
+/// digits or capital letters followed by seven digits; other characters
+/// won't decode.
+pub(crate) fn isrc_frame(isrc: &str, frame: u8) -> Vec<u8> {
+    let mut data = vec![0x03, 0, 0, 0, 0, 0, 0, 0, 0, to_bcd(frame), 0, 0];
+    let bytes = isrc.as_bytes();
+    let mut packed: u32 = 0;
+    for (i, byte) in bytes[..5].iter().enumerate() {
+        packed |= u32::from(byte.wrapping_sub(b'0') & 0x3F) << (26 - i * 6);
+    }
+    data[1] = (packed >> 24) as u8;
+    data[2] = (packed >> 16) as u8;
+    data[3] = (packed >> 8) as u8;
+    data[4] = packed as u8;
+    for (i, digit) in bytes[5..].iter().enumerate() {
+        let digit = digit.wrapping_sub(b'0') & 0x0F;
+        data[5 + i / 2] |= if i % 2 == 0 { digit << 4 } else { digit };
+    }
+    with_crc(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::msf::Msf;
+    use crate::subcode;
+    use crate::subcode::testing;
+
+    fn q(data: Vec<u8>) -> subcode::Subcode {
+        subcode::Subcode {
+            channel: subcode::SubcodeType::Q,
+            data,
+        }
+    }
+
+    #[test]
+    fn test_q_adr() {
+        let subcode = q(vec![0x41, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(Some(1), subcode.q_adr());
+
+        let subcode = subcode::Subcode {
+            channel: subcode::SubcodeType::P,
+            data: vec![0x41, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        };
+        assert_eq!(None, subcode.q_adr());
+    }
+
+    #[test]
+    fn test_q_adr_control_byte() {
+        let subcode = q(vec![0x41, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(Some(0x41), subcode.q_adr_control_byte());
+
+        let subcode = subcode::Subcode {
+            channel: subcode::SubcodeType::R,
+            data: vec![0x41, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        };
+        assert_eq!(None, subcode.q_adr_control_byte());
+    }
+
+    #[test]
+    fn test_q_frame_counter_from_adr_2_frame() {
+        // Catalog number 0123456789012, absolute frame 37
+        let subcode = q(vec![
+            0x02, 0x01, 0x23, 0x45, 0x67, 0x89, 0x01, 0x20, 0x00, 0x37, 0x00, 0x00,
+        ]);
+        assert_eq!(Some(37), subcode.q_frame_counter());
+    }
+
+    #[test]
+    fn test_q_frame_counter_ignores_position_frames() {
+        let subcode = q(vec![
+            0x01, 0x01, 0x01, 0x00, 0x00, 0x05, 0x00, 0x00, 0x02, 0x05, 0x00, 0x00,
+        ]);
+        assert_eq!(None, subcode.q_frame_counter());
+    }
+
+    #[test]
+    fn test_decoding_position_frame() {
+        let subcode = q(testing::position_frame(
+            0x1,
+            2,
+            1,
+            Msf::new(0, 0, 5),
+            Msf::new(3, 2, 5),
+        ));
+        assert!(subcode.q_crc_valid());
+        assert_eq!(Some(1), subcode.q_adr());
+        assert!(subcode.q_control().unwrap().pre_emphasis);
+        assert!(!subcode.q_control().unwrap().data);
+        assert_eq!(Some(subcode::QRegion::Program), subcode.q_region());
+        assert_eq!(Some(2), subcode.q_track());
+        assert_eq!(Some(1), subcode.q_index());
+        assert_eq!(Some(Msf::new(0, 0, 5)), subcode.q_relative());
+        assert_eq!(Some(Msf::new(3, 2, 5)), subcode.q_absolute());
+    }
+
+    #[test]
+    fn test_sector_q() {
+        let sector = testing::sector_with_q(testing::position_frame(
+            0x4,
+            3,
+            1,
+            Msf::new(0, 1, 0),
+            Msf::new(10, 20, 30),
+        ));
+        let q = sector.q().unwrap();
+        assert_eq!(
+            subcode::QSubcode {
+                control: subcode::QControl::from_nibble(0x4),
+                adr: 1,
+                region: Some(subcode::QRegion::Program),
+                track: Some(3),
+                index: Some(1),
+                relative: Some(Msf::new(0, 1, 0)),
+                absolute: Some(Msf::new(10, 20, 30)),
+                crc_valid: true,
+            },
+            q
+        );
+        assert!(q.control.data);
+    }
+
+    #[test]
+    fn test_sector_q_non_position_frame() {
+        let sector = testing::sector_with_q(testing::catalog_frame("0123456789012", 0));
+        let q = sector.q().unwrap();
+        assert_eq!(2, q.adr);
+        assert!(q.crc_valid);
+        assert_eq!(None, q.track);
+        assert_eq!(None, q.absolute);
+    }
+
+    #[test]
+    fn test_valid_q_frames() {
+        let mut sectors = vec![];
+        for i in 0..6u8 {
+            let mut frame =
+                testing::position_frame(0x0, 1, 1, Msf::new(0, 0, i), Msf::new(0, 2, i));
+            if i % 2 == 1 {
+                frame[10] ^= 0xFF;
+            }
+            sectors.push(testing::sector_with_q(frame));
+        }
+        sectors.push(testing::sector_with_q(vec![0; 12]));
+        let disc = subcode::SubcodeData { sectors };
+
+        let frames: Vec<(usize, subcode::QSubcode)> = disc.valid_q_frames().collect();
+        assert_eq!(
+            vec![0, 2, 4],
+            frames.iter().map(|(i, _)| *i).collect::<Vec<_>>()
+        );
+        assert_eq!(Some(Msf::new(0, 2, 4)), frames[2].1.absolute);
+    }
+
+    #[test]
+    fn test_from_positions() {
+        use crate::subcode::{DiscBuilder, Position, QControl, TrackType, LEAD_OUT_TRACK};
+
+        let disc = DiscBuilder::new()
+            .add_track(1, Msf::new(0, 2, 0), 10, TrackType::Audio)
+            .add_track(2, Msf::new(0, 2, 12), 10, TrackType::Data)
+            .lead_out(3)
+            .build();
+        let positions: Vec<Position> = disc.positions().collect();
+        assert_eq!(25, positions.len());
+        assert_eq!(
+            Position {
+                control: QControl::from_nibble(0x0),
+                track: LEAD_OUT_TRACK,
+                index: 1,
+                relative: Msf::new(0, 0, 0),
+                absolute: Msf::new(0, 2, 22),
+            },
+            positions[22]
+        );
+
+        let rebuilt = subcode::SubcodeData::from_positions(&positions);
+        assert_eq!(25, rebuilt.sectors.len());
+        assert!(rebuilt.sectors.iter().all(|s| s.contains_basic_data_only()));
+        assert_eq!(positions, rebuilt.positions().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_sector_q_empty() {
+        let sector = subcode::Sector::parse(vec![0; 96]).unwrap();
+        assert_eq!(None, sector.q());
+    }
+
+    #[test]
+    fn test_crc_detects_corruption() {
+        let mut data = testing::position_frame(0x0, 1, 1, Msf::new(0, 0, 0), Msf::new(0, 2, 0));
+        data[5] ^= 0x01;
+        assert!(!q(data).q_crc_valid());
+    }
+
+    #[test]
+    fn test_lead_out_region() {
+        let subcode = q(testing::position_frame(
+            0x0,
+            subcode::LEAD_OUT_TRACK,
+            1,
+            Msf::new(0, 0, 0),
+            Msf::new(40, 0, 0),
+        ));
+        assert_eq!(Some(subcode::QRegion::LeadOut), subcode.q_region());
+        assert_eq!(Some(subcode::LEAD_OUT_TRACK), subcode.q_track());
+    }
+
+    #[test]
+    fn test_q_catalog() {
+        let subcode = q(testing::catalog_frame("0123456789012", 0));
+        assert!(subcode.q_crc_valid());
+        assert_eq!(Some("0123456789012".to_string()), subcode.q_catalog());
+        assert_eq!(None, subcode.q_track());
+    }
+
+    #[test]
+    fn test_q_isrc() {
+        let subcode = q(testing::isrc_frame("USABC9912345", 0));
+        assert!(subcode.q_crc_valid());
+        assert_eq!(Some("USABC9912345".to_string()), subcode.q_isrc());
+        assert_eq!(None, subcode.q_catalog());
+    }
+
+    #[test]
+    fn test_q_crc_valid_with() {
+        let spec = testing::position_frame(0x0, 1, 1, Msf::new(0, 0, 0), Msf::new(0, 2, 0));
+        // The same frame with its CRC stored plainly, low byte first
+        let mut swapped = spec.clone();
+        swapped[10] = !spec[11];
+        swapped[11] = !spec[10];
+
+        let plain_swapped = subcode::QCrcConfig {
+            invert: false,
+            swap: true,
+        };
+        assert!(q(spec.clone()).q_crc_valid_with(subcode::QCrcConfig::default()));
+        assert!(!q(spec).q_crc_valid_with(plain_swapped));
+        assert!(q(swapped.clone()).q_crc_valid_with(plain_swapped));
+        assert!(!q(swapped).q_crc_valid());
+    }
+
+    #[test]
+    fn test_sector_display() {
+        let frame = testing::position_frame(0x0, 1, 1, Msf::new(0, 0, 5), Msf::new(0, 2, 5));
+        let sector = testing::sector_with_q(frame.clone());
+        assert_eq!(
+            "[01/01 rel 00:00:05 abs 00:02:05 AUDIO crc:ok]",
+            sector.to_string()
+        );
+
+        let mut corrupt = frame;
+        corrupt[0] = 0x41;
+        assert_eq!(
+            "[01/01 rel 00:00:05 abs 00:02:05 DATA crc:bad]",
+            testing::sector_with_q(corrupt).to_string()
+        );
+        assert_eq!(
+            "[ADR 2 AUDIO crc:ok]",
+            testing::sector_with_q(testing::catalog_frame("0123456789012", 0)).to_string()
+        );
+        assert_eq!("[no Q]", testing::sector_with_q(vec![0; 12]).to_string());
+    }
+
+    #[test]
+    fn test_q_position() {
+        let lead_in = q(testing::lead_in_frame(0x0, 0xA0, 0x01, 0x00, 0x00));
+        assert_eq!(
+            Some(subcode::QPosition::LeadIn { point: 0xA0 }),
+            lead_in.q_position()
+        );
+        assert_eq!(Some(0xA0), lead_in.q_point());
+        assert_eq!(None, lead_in.q_index());
+
+        // A POINT naming a track isn't an index either
+        let lead_in = q(testing::lead_in_frame(0x0, 0x05, 0x03, 0x00, 0x00));
+        assert_eq!(Some(0x05), lead_in.q_point());
+        assert_eq!(None, lead_in.q_index());
+
+        let program = q(testing::position_frame(
+            0x0,
+            12,
+            2,
+            Msf::new(0, 0, 0),
+            Msf::new(5, 0, 0),
+        ));
+        assert_eq!(
+            Some(subcode::QPosition::Program {
+                track: 12,
+                index: 2
+            }),
+            program.q_position()
+        );
+        assert_eq!(None, program.q_point());
+    }
+}
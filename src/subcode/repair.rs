@@ -0,0 +1,136 @@
+use crate::bcd::to_bcd;
+use crate::msf::Msf;
+use crate::subcode::q::with_crc;
+use crate::subcode::{QRegion, SubcodeData, SubcodeType};
+
+impl SubcodeData {
+    /// Reconstructs Q frames which fail their CRC check by interpolating
+    /// between the nearest CRC-valid ADR-1 frames on either side. A frame
+    /// is only rebuilt if both neighbours are in the same track and index,
+    /// their absolute times advance by one frame per sector, and their
+    /// relative times count steadily up or down; the rebuilt frame takes
+    /// its control, track and index from the neighbours, its times from
+    /// the progression between them, and a fresh CRC.
+    ///
+    /// Frames which pass their CRC check are never modified. Returns the
+    /// number of frames rebuilt.
+    pub fn interpolate_q_times(&mut self) -> usize {
+        let anchors: Vec<usize> = self
+            .channel_iter(SubcodeType::Q)
+            .enumerate()
+            .filter(|(_, q)| {
+                q.q_crc_valid()
+                    && matches!(
+                        q.q_region(),
+                        Some(QRegion::Program) | Some(QRegion::LeadOut)
+                    )
+                    && q.q_relative().is_some()
+                    && q.q_absolute().is_some()
+            })
+            .map(|(i, _)| i)
+            .collect();
+
+        let mut fixed = 0;
+        for pair in anchors.windows(2) {
+            let (before, after) = (pair[0], pair[1]);
+            if after - before < 2 {
+                continue;
+            }
+            let first = self.sectors[before].channel(SubcodeType::Q).clone();
+            let last = self.sectors[after].channel(SubcodeType::Q);
+            if first.data[..3] != last.data[..3] {
+                continue;
+            }
+
+            // Both unwraps are safe since anchors have both times
+            let span = (after - before) as i64;
+            let absolute = i64::from(first.q_absolute().unwrap().frames());
+            if i64::from(last.q_absolute().unwrap().frames()) - absolute != span {
+                continue;
+            }
+            let relative = i64::from(first.q_relative().unwrap().frames());
+            let direction = match i64::from(last.q_relative().unwrap().frames()) - relative {
+                delta if delta == span => 1,
+                delta if delta == -span => -1,
+                _ => continue,
+            };
+
+            for i in before + 1..after {
+                let q = self.sectors[i].channel_mut(SubcodeType::Q);
+                if q.q_crc_valid() {
+                    continue;
+                }
+                let offset = (i - before) as i64;
+                let relative = Msf::from_frames((relative + direction * offset) as u32);
+                let absolute = Msf::from_frames((absolute + offset) as u32);
+
+                let mut data = first.data.clone();
+                data[3] = to_bcd(relative.minute);
+                data[4] = to_bcd(relative.second);
+                data[5] = to_bcd(relative.frame);
+                data[6] = 0;
+                data[7] = to_bcd(absolute.minute);
+                data[8] = to_bcd(absolute.second);
+                data[9] = to_bcd(absolute.frame);
+                q.data = with_crc(data);
+                fixed += 1;
+            }
+        }
+
+        fixed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::subcode::{DiscBuilder, TrackType};
+
+    fn disc() -> crate::subcode::SubcodeData {
+        DiscBuilder::new()
+            .append_track(1, 0, 10, TrackType::Audio)
+            .append_track(2, 4, 10, TrackType::Audio)
+            .build()
+    }
+
+    #[test]
+    fn test_interpolate_q_times() {
+        let original = disc();
+        let mut damaged = disc();
+        damaged.sectors[5].codes[1].data[7] ^= 0xFF;
+        assert!(!damaged.sectors[5].codes[1].q_crc_valid());
+
+        assert_eq!(1, damaged.interpolate_q_times());
+        assert_eq!(
+            original.sectors[5].codes[1].data,
+            damaged.sectors[5].codes[1].data
+        );
+        assert_eq!(0, damaged.interpolate_q_times());
+    }
+
+    #[test]
+    fn test_interpolate_q_times_in_pregap() {
+        let original = disc();
+        let mut damaged = disc();
+        // Relative time counts down through track 2's pregap
+        damaged.sectors[11].codes[1].data[4] ^= 0xFF;
+        damaged.sectors[12].codes[1].data[4] ^= 0xFF;
+
+        assert_eq!(2, damaged.interpolate_q_times());
+        for i in 11..13 {
+            assert_eq!(
+                original.sectors[i].codes[1].data,
+                damaged.sectors[i].codes[1].data
+            );
+        }
+    }
+
+    #[test]
+    fn test_interpolate_q_times_across_tracks() {
+        let mut damaged = disc();
+        // The neighbours of the pregap's first sector are in different
+        // tracks, so there's nothing to interpolate between
+        damaged.sectors[10].codes[1].data[7] ^= 0xFF;
+        assert_eq!(0, damaged.interpolate_q_times());
+        assert!(!damaged.sectors[10].codes[1].q_crc_valid());
+    }
+}
@@ -0,0 +1,148 @@
+use std::ops::Range;
+
+use crate::subcode::{QRegion, Subcode, SubcodeData, SubcodeType};
+use crate::toc::ADR_EXTENDED;
+
+impl SubcodeData {
+    /// Splits the disc into sessions, returning the range of sectors each
+    /// one covers. Every session after the first begins with its lead-in;
+    /// the first begins at the start of the dump whether or not its own
+    /// lead-in was captured. A session runs until the next one's lead-in,
+    /// so it includes its own lead-out.
+    ///
+    /// Sectors with bad CRCs never start a session. Returns an empty list
+    /// for an empty dump.
+    pub fn sessions(&self) -> Vec<Range<usize>> {
+        let mut starts = vec![];
+        let mut in_lead_in = false;
+
+        for (i, q) in self.channel_iter(SubcodeType::Q).enumerate() {
+            if !q.q_crc_valid() {
+                continue;
+            }
+            let lead_in = is_lead_in(q);
+            if lead_in && !in_lead_in && i > 0 {
+                starts.push(i);
+            }
+            in_lead_in = lead_in;
+        }
+
+        if self.sectors.is_empty() {
+            return vec![];
+        }
+        starts.insert(0, 0);
+        starts.push(self.sectors.len());
+        starts.windows(2).map(|pair| pair[0]..pair[1]).collect()
+    }
+
+    /// Returns the sectors between each pair of consecutive sessions: from
+    /// the start of one session's lead-out, or its end if no lead-out was
+    /// captured, up to the first program area sector of the next. This
+    /// covers the lead-out and the next session's lead-in.
+    pub fn session_gaps(&self) -> Vec<Range<usize>> {
+        let first_in_region = |range: &Range<usize>, region: QRegion| {
+            self.sectors[range.clone()]
+                .iter()
+                .position(|sector| {
+                    let q = sector.channel(SubcodeType::Q);
+                    q.q_crc_valid() && q.q_region() == Some(region)
+                })
+                .map(|offset| range.start + offset)
+        };
+
+        self.sessions()
+            .windows(2)
+            .map(|pair| {
+                let (current, next) = (&pair[0], &pair[1]);
+                let start = first_in_region(current, QRegion::LeadOut).unwrap_or(current.end);
+                let end = first_in_region(next, QRegion::Program).unwrap_or(next.end);
+                start..end
+            })
+            .collect()
+    }
+
+    /// Returns the first sector of a CD-Extra disc's data session: the
+    /// start of the second session's lead-in, provided that session
+    /// contains a data track. Returns `None` for single-session discs and
+    /// for multisession discs whose second session is audio.
+    pub fn cdextra_data_session_start(&self) -> Option<usize> {
+        let session = self.sessions().into_iter().nth(1)?;
+        let has_data = self.sectors[session.clone()].iter().any(|sector| {
+            let q = sector.channel(SubcodeType::Q);
+            q.q_crc_valid()
+                && q.q_region() == Some(QRegion::Program)
+                && q.q_control().is_some_and(|control| control.data)
+        });
+
+        if has_data {
+            Some(session.start)
+        } else {
+            None
+        }
+    }
+}
+
+fn is_lead_in(q: &Subcode) -> bool {
+    match q.q_adr() {
+        Some(1) => q.q_region() == Some(QRegion::LeadIn),
+        Some(ADR_EXTENDED) => q.data[1] == 0,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::msf::Msf;
+    use crate::subcode::testing;
+    use crate::subcode::{DiscBuilder, SubcodeData, TrackType};
+
+    fn session(start: Msf, number: u8, control: u8, lead_in: usize) -> SubcodeData {
+        let mut disc = DiscBuilder::new()
+            .start(start)
+            .append_track(number, 2, 10, TrackType::Audio)
+            .control(number, control)
+            .lead_out(3)
+            .build();
+        let lead_in = (0..lead_in)
+            .map(|_| testing::sector_with_q(testing::lead_in_frame(control, 0xA0, 1, 0, 0)));
+        disc.sectors.splice(0..0, lead_in);
+        disc
+    }
+
+    fn cd_extra(second_control: u8) -> SubcodeData {
+        let mut disc = session(Msf::new(0, 2, 0), 1, 0x0, 4);
+        disc.sectors
+            .extend(session(Msf::new(10, 0, 0), 2, second_control, 5).sectors);
+        disc
+    }
+
+    #[test]
+    fn test_sessions() {
+        // 4 lead-in + 2 pregap + 10 + 3 lead-out, then 5 lead-in + the rest
+        let disc = cd_extra(0x4);
+        assert_eq!(vec![0..19, 19..39], disc.sessions());
+
+        let single = session(Msf::new(0, 2, 0), 1, 0x0, 0);
+        assert_eq!(vec![0..15], single.sessions());
+        assert!(SubcodeData::new().sessions().is_empty());
+    }
+
+    #[test]
+    fn test_cdextra_data_session_start() {
+        assert_eq!(Some(19), cd_extra(0x4).cdextra_data_session_start());
+        assert_eq!(None, cd_extra(0x0).cdextra_data_session_start());
+        assert_eq!(
+            None,
+            session(Msf::new(0, 2, 0), 1, 0x4, 4).cdextra_data_session_start()
+        );
+    }
+
+    #[test]
+    fn test_session_gaps() {
+        // Session 1's lead-out starts at 16, and session 2's pregap at 24
+        assert_eq!(vec![16..24], cd_extra(0x4).session_gaps());
+        assert!(session(Msf::new(0, 2, 0), 1, 0x0, 4)
+            .session_gaps()
+            .is_empty());
+    }
+}
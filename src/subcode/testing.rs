@@ -0,0 +1,133 @@
+//! Helpers for assembling synthetic subcode in tests.
+
+use crate::cdtext::CdTextPack;
+use crate::crc;
+use crate::rw::RwPack;
+use crate::subcode::{builder, Sector, SubcodeData};
+
+pub(crate) use crate::subcode::q::{catalog_frame, isrc_frame, position_frame, with_crc};
+
+/// Builds a lead-in ADR-1 frame for the given POINT, with its PMIN, PSEC
+/// and PFRAME bytes stored as-is.
+pub fn lead_in_frame(control: u8, point: u8, pmin: u8, psec: u8, pframe: u8) -> Vec<u8> {
+    with_crc(vec![
+        control << 4 | 1,
+        0,
+        point,
+        0,
+        0,
+        0,
+        0,
+        pmin,
+        psec,
+        pframe,
+        0,
+        0,
+    ])
+}
+
+/// Builds a sector with the given P fill byte and Q frame, and empty R-W.
+pub fn sector(p: u8, q: Vec<u8>) -> Sector {
+    builder::sector(p, q)
+}
+
+/// Builds a sector with an empty P channel and the given Q frame.
+pub fn sector_with_q(q: Vec<u8>) -> Sector {
+    sector(0, q)
+}
+
+/// Builds a sector whose R-W channels hold the given packs.
+pub fn sector_with_packs(packs: &[RwPack; 4]) -> Sector {
+    let mut data = vec![0; 96];
+    for (p, pack) in packs.iter().enumerate() {
+        for (s, symbol) in pack.symbols.iter().enumerate() {
+            let frame = p * 24 + s;
+            for channel in 0..6 {
+                if symbol & (0x20 >> channel) != 0 {
+                    data[24 + channel * 12 + frame / 8] |= 0x80 >> (frame % 8);
+                }
+            }
+        }
+    }
+    Sector::parse(data).unwrap()
+}
+
+/// Builds a pack from its header symbols and up to 12 data bytes.
+pub fn pack(mode: u8, item: u8, instruction: u8, bytes: &[u8]) -> RwPack {
+    let mut symbols = [0; 24];
+    symbols[0] = mode << 3 | item;
+    symbols[1] = instruction;
+    for (i, byte) in bytes.iter().enumerate() {
+        for bit in 0..8 {
+            if byte & (0x80 >> bit) != 0 {
+                let position = i * 8 + bit;
+                symbols[4 + position / 6] |= 0x20 >> (position % 6);
+            }
+        }
+    }
+    RwPack { symbols }
+}
+
+/// Builds a CD-TEXT pack from its header and text, with a valid CRC.
+pub fn cdtext_pack(header: [u8; 4], text: [u8; 12]) -> CdTextPack {
+    let mut bytes = [0; 18];
+    bytes[..4].copy_from_slice(&header);
+    bytes[4..16].copy_from_slice(&text);
+    let crc = !crc::crc16(&bytes[..16]);
+    bytes[16] = (crc >> 8) as u8;
+    bytes[17] = crc as u8;
+    CdTextPack { bytes }
+}
+
+/// Splits a stream of NUL-terminated album and track strings into
+/// CD-TEXT packs, starting at the album (track 0).
+pub fn cdtext_packs(
+    pack_type: u8,
+    block: u8,
+    first_sequence: u8,
+    stream: &[u8],
+) -> Vec<CdTextPack> {
+    let mut packs = vec![];
+    let mut track = 0;
+    let mut position = 0;
+
+    for (i, chunk) in stream.chunks(12).enumerate() {
+        let mut text = [0; 12];
+        text[..chunk.len()].copy_from_slice(chunk);
+        let header = [
+            pack_type,
+            track,
+            first_sequence + i as u8,
+            block << 4 | position.min(15) as u8,
+        ];
+        packs.push(cdtext_pack(header, text));
+
+        for byte in chunk {
+            if *byte == 0 {
+                track += 1;
+                position = 0;
+            } else {
+                position += 1;
+            }
+        }
+    }
+
+    packs
+}
+
+/// Builds a disc whose R-W channels carry the given CD-TEXT packs.
+pub fn disc_with_cdtext(packs: &[CdTextPack]) -> SubcodeData {
+    let empty = RwPack { symbols: [0; 24] };
+    let sectors = packs
+        .chunks(4)
+        .map(|chunk| {
+            let mut rw = [empty; 4];
+            for (i, pack) in chunk.iter().enumerate() {
+                rw[i] = RwPack::from_bytes(&pack.bytes);
+            }
+            sector_with_packs(&rw)
+        })
+        .collect();
+
+    SubcodeData { sectors }
+}
@@ -0,0 +1,807 @@
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use crate::msf::Msf;
+use crate::subcode::{QControl, QRegion, Sector, SubcodeData, SubcodeType};
+
+/// The kind of content a track holds, according to its Q control bits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "bincode", derive(serde::Serialize, serde::Deserialize))]
+pub enum TrackType {
+    Audio,
+    Data,
+    /// A data track on a CD-i disc. These can only be told apart from
+    /// other data tracks by the disc type given in the lead-in.
+    CdInteractive,
+}
+
+/// Length in sectors of the two-second pregap the Red Book requires
+/// before track 1.
+pub const STANDARD_TRACK1_PREGAP: usize = 150;
+
+/// Number of stereo audio samples in each sector.
+const SAMPLES_PER_SECTOR: usize = 588;
+
+/// How a flag that can vary from sector to sector is decided for a whole
+/// track, based on how many of the track's sectors have it set.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum FlagResolution {
+    /// Set if more than half of the sectors have it set.
+    #[default]
+    Majority,
+    /// Set if any sector has it set.
+    Any,
+    /// Set only if every sector has it set.
+    All,
+    /// Set if at least this fraction of the sectors, from 0.0 to 1.0,
+    /// have it set.
+    Threshold(f64),
+}
+
+impl FlagResolution {
+    /// Decides a flag which is set in `set` out of `total` sectors. A flag
+    /// is never set when there are no sectors.
+    pub fn resolve(self, set: usize, total: usize) -> bool {
+        if total == 0 {
+            return false;
+        }
+        match self {
+            FlagResolution::Majority => set * 2 > total,
+            FlagResolution::Any => set > 0,
+            FlagResolution::All => set == total,
+            FlagResolution::Threshold(fraction) => set as f64 >= fraction * total as f64,
+        }
+    }
+}
+
+/// A track found by scanning the Q channel. Sector positions are indices
+/// into `SubcodeData::sectors`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "bincode", derive(serde::Serialize, serde::Deserialize))]
+pub struct Track {
+    pub number: u8,
+    pub track_type: TrackType,
+    pub control: QControl,
+    /// The first index 00 (pregap) sector, if the dump contains any.
+    pub pregap_start: Option<usize>,
+    /// The first index 01 sector.
+    pub start: usize,
+    /// One past the last sector of the track.
+    pub end: usize,
+    pub isrc: Option<String>,
+}
+
+impl Track {
+    /// Returns the first sector belonging to the track, including its pregap.
+    pub fn first_sector(&self) -> usize {
+        self.pregap_start.unwrap_or(self.start)
+    }
+}
+
+impl SubcodeData {
+    /// Finds every program track with a CRC-valid index 01 frame, in disc
+    /// order. Frames failing their CRC are ignored. Data tracks are
+    /// reported as `TrackType::CdInteractive` if `is_cdi` is true.
+    ///
+    /// Each track runs until the first sector of the next track, or, for
+    /// the last track, until the lead-out or the end of the dump.
+    /// ISRCs are assigned to whichever track was playing when the ADR-3
+    /// frame was read.
+    pub fn tracks(&self) -> Vec<Track> {
+        let mut tracks: Vec<Track> = vec![];
+        let mut pregaps: Vec<(u8, usize)> = vec![];
+        let mut current: Option<u8> = None;
+        let mut lead_out = None;
+
+        for (i, sector) in self.sectors.iter().enumerate() {
+            let q = sector.channel(SubcodeType::Q);
+            if !q.q_crc_valid() {
+                continue;
+            }
+
+            if let Some(isrc) = q.q_isrc() {
+                if let Some(track) = tracks.iter_mut().find(|t| Some(t.number) == current) {
+                    track.isrc.get_or_insert(isrc);
+                }
+                continue;
+            }
+
+            match q.q_region() {
+                Some(QRegion::Program) => (),
+                Some(QRegion::LeadOut) => {
+                    lead_out.get_or_insert(i);
+                    continue;
+                }
+                _ => continue,
+            }
+            let (number, index) = match (q.q_track(), q.q_index()) {
+                (Some(number), Some(index)) => (number, index),
+                _ => continue,
+            };
+            current = Some(number);
+
+            if index == 0 {
+                if !pregaps.iter().any(|(n, _)| *n == number) {
+                    pregaps.push((number, i));
+                }
+                continue;
+            }
+            if tracks.iter().any(|t| t.number == number) {
+                continue;
+            }
+
+            // Unwrapping is safe since we've confirmed this is a Q frame
+            let control = q.q_control().unwrap();
+            tracks.push(Track {
+                number,
+                track_type: if control.data {
+                    TrackType::Data
+                } else {
+                    TrackType::Audio
+                },
+                control,
+                pregap_start: pregaps
+                    .iter()
+                    .find(|(n, _)| *n == number)
+                    .map(|(_, start)| *start),
+                start: i,
+                end: i,
+                isrc: None,
+            });
+        }
+
+        if self.is_cdi() {
+            for track in tracks.iter_mut() {
+                if track.track_type == TrackType::Data {
+                    track.track_type = TrackType::CdInteractive;
+                }
+            }
+        }
+
+        tracks.sort_by_key(|t| t.start);
+        let mut end = lead_out.unwrap_or(self.sectors.len());
+        for track in tracks.iter_mut().rev() {
+            track.end = end.max(track.start);
+            end = track.first_sector();
+        }
+
+        tracks
+    }
+
+    /// Maps each program track to the indices found within it, as pairs of
+    /// index number and the absolute time of the index's first CRC-valid
+    /// frame. Pregaps appear as index 00. Each track's indices are sorted
+    /// by number.
+    pub fn track_index_map(&self) -> BTreeMap<u8, Vec<(u8, Msf)>> {
+        let mut map: BTreeMap<u8, Vec<(u8, Msf)>> = BTreeMap::new();
+
+        for q in self.channel_iter(SubcodeType::Q) {
+            if !q.q_crc_valid() || q.q_region() != Some(QRegion::Program) {
+                continue;
+            }
+            let (track, index, absolute) = match (q.q_track(), q.q_index(), q.q_absolute()) {
+                (Some(track), Some(index), Some(absolute)) => (track, index, absolute),
+                _ => continue,
+            };
+            let indices = map.entry(track).or_default();
+            if !indices.iter().any(|(i, _)| *i == index) {
+                indices.push((index, absolute));
+            }
+        }
+
+        for indices in map.values_mut() {
+            indices.sort_by_key(|(index, _)| *index);
+        }
+
+        map
+    }
+
+    /// Decides each of a track's control bits from every CRC-valid
+    /// ADR-1 frame in its index 01 onward, rather than just the first one
+    /// as `tracks` does. Returns the track's existing control bits if it
+    /// has no such frames.
+    pub fn track_control(&self, track: &Track, resolution: FlagResolution) -> QControl {
+        let controls: Vec<QControl> = self.sectors[track.start..track.end]
+            .iter()
+            .map(|sector| sector.channel(SubcodeType::Q))
+            .filter(|q| q.q_crc_valid() && q.q_region() == Some(QRegion::Program))
+            .filter_map(|q| q.q_control())
+            .collect();
+        if controls.is_empty() {
+            return track.control;
+        }
+
+        let resolve = |bit: fn(&QControl) -> bool| {
+            resolution.resolve(controls.iter().filter(|c| bit(c)).count(), controls.len())
+        };
+        QControl {
+            four_channel: resolve(|c| c.four_channel),
+            data: resolve(|c| c.data),
+            copy_permitted: resolve(|c| c.copy_permitted),
+            pre_emphasis: resolve(|c| c.pre_emphasis),
+        }
+    }
+
+    /// Lists every sector at which the Q control bits change, along with
+    /// the control bits before and after. Frames of every ADR carry
+    /// control bits, but those failing their CRC are skipped.
+    pub fn control_transitions(&self) -> Vec<(usize, QControl, QControl)> {
+        let mut transitions = vec![];
+        let mut previous = None;
+
+        for (i, q) in self.channel_iter(SubcodeType::Q).enumerate() {
+            if !q.q_crc_valid() {
+                continue;
+            }
+            // Unwrapping is safe since this is always the Q channel
+            let control = q.q_control().unwrap();
+            if let Some(before) = previous {
+                if before != control {
+                    transitions.push((i, before, control));
+                }
+            }
+            previous = Some(control);
+        }
+
+        transitions
+    }
+
+    /// Counts the sectors (frames) of actual track content: everything
+    /// from each track's index 01 to its end. Lead-in, lead-out and
+    /// pregaps aren't counted. Since this is based on `tracks`, frames
+    /// within a track that fail their CRC or carry a catalog number or
+    /// ISRC are still counted.
+    pub fn program_frame_count(&self) -> usize {
+        self.tracks()
+            .iter()
+            .map(|track| track.end - track.start)
+            .sum()
+    }
+
+    /// Returns the program area: the sectors from track 1's index 01
+    /// through the end of the last track. Any lead-in, the pregap of
+    /// track 1 and any lead-out are excluded. Returns an empty slice if
+    /// the start of track 1 can't be found.
+    pub fn program_area(&self) -> &[Sector] {
+        let tracks = self.tracks();
+        match (tracks.iter().find(|t| t.number == 1), tracks.last()) {
+            (Some(first), Some(last)) => &self.sectors[first.start..last.end],
+            _ => &[],
+        }
+    }
+
+    /// Returns the number of track 1 pregap (index 00) sectors in the
+    /// dump, or `None` if track 1 or its pregap can't be found. A dump
+    /// which starts part way through the pregap gives a shorter count.
+    pub fn track1_pregap_sectors(&self) -> Option<usize> {
+        let track = self.tracks().into_iter().find(|track| track.number == 1)?;
+        Some(track.start - track.pregap_start?)
+    }
+
+    /// Returns whether track 1's pregap is longer than the standard two
+    /// seconds. This usually means the disc has a hidden track in the
+    /// pregap, but can also come from malformed subcode.
+    pub fn has_extended_track1_pregap(&self) -> bool {
+        self.track1_pregap_sectors()
+            .is_some_and(|sectors| sectors > STANDARD_TRACK1_PREGAP)
+    }
+
+    /// Returns the gap between each pair of consecutive tracks, as the
+    /// numbers of the tracks on either side followed by the length of the
+    /// second track's pregap (index 00). Gapless transitions are listed
+    /// with a length of zero, so a typical gapped album gives two seconds
+    /// between every pair. Track 1's pregap isn't between tracks, so isn't
+    /// included.
+    pub fn intertrack_gaps(&self) -> Vec<(u8, u8, Msf)> {
+        self.tracks()
+            .windows(2)
+            .map(|pair| {
+                let gap = pair[1].start - pair[1].first_sector();
+                (pair[0].number, pair[1].number, Msf::from_frames(gap as u32))
+            })
+            .collect()
+    }
+
+    /// Returns the numbers of tracks which `tracks` finds to have no
+    /// sectors of their own, because the next track begins where they do.
+    /// Real tracks are at least four seconds long, so these point to
+    /// damaged or badly mastered subcode.
+    pub fn zero_length_tracks(&self) -> Vec<u8> {
+        self.tracks()
+            .iter()
+            .filter(|track| track.end <= track.start)
+            .map(|track| track.number)
+            .collect()
+    }
+
+    /// Returns the fraction of program-area sectors that are in a pregap
+    /// (index 00), from 0.0 to 1.0. Only sectors with a CRC-valid ADR-1
+    /// frame are counted; returns 0.0 if there are none.
+    pub fn pregap_ratio(&self) -> f64 {
+        let indices: Vec<u8> = self
+            .channel_iter(SubcodeType::Q)
+            .filter(|q| q.q_crc_valid() && q.q_region() == Some(QRegion::Program))
+            .filter_map(|q| q.q_index())
+            .collect();
+        if indices.is_empty() {
+            return 0.0;
+        }
+
+        let pregap = indices.iter().filter(|index| **index == 0).count();
+        pregap as f64 / indices.len() as f64
+    }
+
+    /// Returns whether the dump includes any of the lead-out: a CRC-valid
+    /// ADR-1 frame with the lead-out track number. Without it, the end of
+    /// the last track can't be told from the end of the dump, so the disc
+    /// length has to come from the TOC instead.
+    pub fn has_leadout_sectors(&self) -> bool {
+        self.channel_iter(SubcodeType::Q)
+            .any(|q| q.q_crc_valid() && q.q_region() == Some(QRegion::LeadOut))
+    }
+
+    /// Returns the index of the first sector whose CRC-valid Q frame is
+    /// positioned within the given program track, including its pregap.
+    /// Returns `None` if the track never appears.
+    pub fn find_track(&self, track: u8) -> Option<usize> {
+        self.channel_iter(SubcodeType::Q).position(|q| {
+            q.q_crc_valid() && q.q_region() == Some(QRegion::Program) && q.q_track() == Some(track)
+        })
+    }
+
+    /// Returns the sector range of each data track, from its index 01 to
+    /// its end, as flagged by the track's Q control bits. Pregaps aren't
+    /// included, since they're often recorded in the previous track's
+    /// format.
+    pub fn data_track_ranges(&self) -> Vec<Range<usize>> {
+        self.tracks()
+            .iter()
+            .filter(|track| track.track_type != TrackType::Audio)
+            .map(|track| track.start..track.end)
+            .collect()
+    }
+
+    /// Lists the track numbers which appear in more than one separate run
+    /// of sectors, which suggests the dump is corrupt or was misassembled.
+    /// Only CRC-valid ADR-1 frames in the program area are considered, so
+    /// a run isn't broken by damaged frames or catalog number and ISRC
+    /// frames. Numbers are returned in ascending order.
+    pub fn duplicate_track_regions(&self) -> Vec<u8> {
+        let mut seen = vec![];
+        let mut duplicates = vec![];
+        let mut current = None;
+
+        for q in self.channel_iter(SubcodeType::Q) {
+            if !q.q_crc_valid() || q.q_region() != Some(QRegion::Program) {
+                continue;
+            }
+            let number = match q.q_track() {
+                Some(number) => number,
+                None => continue,
+            };
+            if current == Some(number) {
+                continue;
+            }
+            current = Some(number);
+
+            if seen.contains(&number) {
+                if !duplicates.contains(&number) {
+                    duplicates.push(number);
+                }
+            } else {
+                seen.push(number);
+            }
+        }
+
+        duplicates.sort_unstable();
+        duplicates
+    }
+
+    /// Returns the range of sectors holding the program area's audio once
+    /// a drive's read offset, in samples, is accounted for. A positive
+    /// offset means the audio for a given subcode position appears that
+    /// many samples later in the main channel. Since there are 588 stereo
+    /// samples per sector, an offset that isn't a whole number of sectors
+    /// widens the range to cover the partial sectors at both ends.
+    ///
+    /// The range is clamped to the sectors in the dump, and is empty if
+    /// the program area can't be found.
+    pub fn verification_range(&self, read_offset_samples: i32) -> Range<usize> {
+        let tracks = self.tracks();
+        let (start, end) = match (tracks.iter().find(|t| t.number == 1), tracks.last()) {
+            (Some(first), Some(last)) => (first.start, last.end),
+            _ => return 0..0,
+        };
+
+        let samples = SAMPLES_PER_SECTOR as i64;
+        let offset = i64::from(read_offset_samples);
+        let first_sample = start as i64 * samples + offset;
+        let last_sample = end as i64 * samples + offset;
+        let clamp = |sector: i64| sector.max(0).min(self.sectors.len() as i64) as usize;
+
+        clamp(first_sample.div_euclid(samples))
+            ..clamp((last_sample + samples - 1).div_euclid(samples))
+    }
+
+    /// Returns the sample offset of each track's index 01 into a single
+    /// WAV holding the whole dump's audio, along with its track number.
+    /// Offsets count stereo samples from the dump's first sector, 588 to
+    /// a sector, which is handy for building chapter lists for audio
+    /// editors.
+    pub fn chapters_with_samples(&self) -> Vec<(u8, u64)> {
+        self.tracks()
+            .iter()
+            .map(|track| (track.number, (track.start * SAMPLES_PER_SECTOR) as u64))
+            .collect()
+    }
+
+    /// Returns the media catalog number from the first CRC-valid
+    /// ADR-2 frame on the disc, if any.
+    pub fn catalog(&self) -> Option<String> {
+        self.channel_iter(SubcodeType::Q)
+            .filter(|q| q.q_crc_valid())
+            .find_map(|q| q.q_catalog())
+    }
+
+    /// Returns the index of every sector holding a CRC-valid ADR-3 frame
+    /// with a decodable ISRC. Comparing these against each track's range
+    /// shows where a dump failed to capture ISRC frames.
+    pub fn isrc_sector_indices(&self) -> Vec<usize> {
+        self.channel_iter(SubcodeType::Q)
+            .enumerate()
+            .filter(|(_, q)| q.q_crc_valid() && q.q_isrc().is_some())
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::msf::Msf;
+    use crate::subcode;
+    use crate::subcode::testing;
+    use crate::subcode::{DiscBuilder, FlagResolution, QControl, TrackType};
+
+    fn two_track_disc() -> subcode::SubcodeData {
+        DiscBuilder::new()
+            .append_track(1, 0, 10, TrackType::Audio)
+            .append_track(2, 3, 5, TrackType::Data)
+            .lead_out(2)
+            .build()
+    }
+
+    #[test]
+    fn test_tracks() {
+        let tracks = two_track_disc().tracks();
+        assert_eq!(2, tracks.len());
+
+        assert_eq!(1, tracks[0].number);
+        assert_eq!(subcode::TrackType::Audio, tracks[0].track_type);
+        assert_eq!(None, tracks[0].pregap_start);
+        assert_eq!(0, tracks[0].start);
+        assert_eq!(10, tracks[0].end);
+
+        assert_eq!(2, tracks[1].number);
+        assert_eq!(subcode::TrackType::Data, tracks[1].track_type);
+        assert_eq!(Some(10), tracks[1].pregap_start);
+        assert_eq!(13, tracks[1].start);
+        assert_eq!(18, tracks[1].end);
+    }
+
+    #[test]
+    fn test_tracks_ignore_bad_crcs() {
+        let mut disc = two_track_disc();
+        // Corrupt the first index 01 frame of track 2; the next one
+        // should be used instead.
+        disc.sectors[13].codes[1].data[4] ^= 0xFF;
+        let tracks = disc.tracks();
+        assert_eq!(14, tracks[1].start);
+    }
+
+    #[test]
+    fn test_cdi_tracks() {
+        let mut disc = two_track_disc();
+        let lead_in: Vec<subcode::Sector> = (0..3)
+            .map(|_| testing::sector_with_q(testing::lead_in_frame(0x4, 0xA0, 0x01, 0x10, 0x00)))
+            .collect();
+        disc.sectors.splice(0..0, lead_in);
+
+        assert!(disc.is_cdi());
+        let tracks = disc.tracks();
+        assert_eq!(subcode::TrackType::Audio, tracks[0].track_type);
+        assert_eq!(subcode::TrackType::CdInteractive, tracks[1].track_type);
+    }
+
+    #[test]
+    fn test_track_control_resolution() {
+        // Pre-emphasis is set on 6 of the 10 sectors of track 1
+        let mut disc = two_track_disc();
+        for i in 0..6 {
+            disc.sectors[i].codes[1].data = testing::position_frame(
+                0x1,
+                1,
+                1,
+                Msf::from_frames(i as u32),
+                Msf::from_frames(Msf::new(0, 2, 0).frames() + i as u32),
+            );
+        }
+        let track = &disc.tracks()[0];
+
+        let resolve = |resolution| disc.track_control(track, resolution).pre_emphasis;
+        assert!(resolve(FlagResolution::Majority));
+        assert!(resolve(FlagResolution::Any));
+        assert!(!resolve(FlagResolution::All));
+        assert!(resolve(FlagResolution::Threshold(0.5)));
+        assert!(!resolve(FlagResolution::Threshold(0.9)));
+        assert!(!disc.track_control(track, FlagResolution::Any).data);
+    }
+
+    #[test]
+    fn test_duplicate_track_regions() {
+        let mut disc = (1..=4)
+            .fold(DiscBuilder::new(), |builder, number| {
+                builder.append_track(number, 0, 5, TrackType::Audio)
+            })
+            .build();
+        assert!(disc.duplicate_track_regions().is_empty());
+
+        // Track 3 turns up again after track 4
+        let copy: Vec<subcode::Sector> = disc.sectors[10..15].to_vec();
+        disc.sectors.extend(copy);
+        assert_eq!(vec![3], disc.duplicate_track_regions());
+    }
+
+    #[test]
+    fn test_data_track_ranges() {
+        let disc = DiscBuilder::new()
+            .append_track(1, 0, 10, TrackType::Audio)
+            .append_track(2, 3, 5, TrackType::Data)
+            .append_track(3, 2, 4, TrackType::Audio)
+            .lead_out(2)
+            .build();
+        assert_eq!(vec![13..18], disc.data_track_ranges());
+    }
+
+    #[test]
+    fn test_verification_range() {
+        let disc = subcode::DiscBuilder::new()
+            .start(Msf::new(0, 0, 0))
+            .add_track(1, Msf::new(0, 2, 0), 300, subcode::TrackType::Audio)
+            .lead_out(10)
+            .build();
+
+        assert_eq!(150..450, disc.verification_range(0));
+        assert_eq!(152..452, disc.verification_range(1176));
+        assert_eq!(149..450, disc.verification_range(-30));
+        assert_eq!(151..452, disc.verification_range(667));
+        // Clamped to the end of the dump
+        assert_eq!(170..460, disc.verification_range(588 * 20));
+    }
+
+    #[test]
+    fn test_intertrack_gaps() {
+        let disc = subcode::DiscBuilder::new()
+            .add_track(1, Msf::new(0, 2, 0), 300, subcode::TrackType::Audio)
+            .add_track(2, Msf::new(0, 8, 0), 300, subcode::TrackType::Audio)
+            .add_track(3, Msf::new(0, 12, 0), 300, subcode::TrackType::Audio)
+            .build();
+        assert_eq!(
+            vec![(1, 2, Msf::new(0, 2, 0)), (2, 3, Msf::new(0, 0, 0))],
+            disc.intertrack_gaps()
+        );
+        assert!(subcode::SubcodeData::new().intertrack_gaps().is_empty());
+    }
+
+    #[test]
+    fn test_chapters_with_samples() {
+        // Track 2 starts at sector 13, after its three-sector pregap
+        assert_eq!(
+            vec![(1, 0), (2, 13 * 588)],
+            two_track_disc().chapters_with_samples()
+        );
+    }
+
+    #[test]
+    fn test_control_transitions() {
+        let mut disc = two_track_disc();
+        // A damaged frame doesn't count as a change
+        disc.sectors[11].codes[1].data[0] = 0x01;
+
+        // Track 2 switches to data, and the lead-out back to audio
+        let audio = QControl::from_nibble(0x0);
+        let data = QControl::from_nibble(0x4);
+        assert_eq!(
+            vec![(10, audio, data), (18, data, audio)],
+            disc.control_transitions()
+        );
+    }
+
+    #[test]
+    fn test_find_track() {
+        let mut disc = two_track_disc();
+        assert_eq!(Some(0), disc.find_track(1));
+        assert_eq!(Some(10), disc.find_track(2));
+        assert_eq!(None, disc.find_track(3));
+
+        disc.sectors[10].codes[1].data[4] ^= 0xFF;
+        assert_eq!(Some(11), disc.find_track(2));
+    }
+
+    #[test]
+    fn test_program_frame_count() {
+        let mut disc = two_track_disc();
+        // A damaged frame within a track still counts
+        disc.sectors[4].codes[1].data[4] ^= 0xFF;
+        // 10 sectors of track 1, and 5 of track 2 after its 3-sector
+        // pregap; the 2 lead-out sectors are excluded.
+        assert_eq!(20, disc.sectors.len());
+        assert_eq!(15, disc.program_frame_count());
+    }
+
+    #[test]
+    fn test_program_area() {
+        let mut disc = DiscBuilder::new()
+            .start(Msf::new(0, 0, 0))
+            .append_track(1, 4, 10, TrackType::Audio)
+            .append_track(2, 2, 5, TrackType::Audio)
+            .lead_out(3)
+            .build();
+        let lead_in: Vec<subcode::Sector> = (0..6)
+            .map(|i| {
+                testing::sector_with_q(testing::position_frame(
+                    0,
+                    0,
+                    1,
+                    Msf::from_frames(i),
+                    Msf::new(0, 2, 0),
+                ))
+            })
+            .collect();
+        disc.sectors.splice(0..0, lead_in);
+        assert_eq!(30, disc.sectors.len());
+
+        let program_area = disc.program_area();
+        assert_eq!(17, program_area.len());
+        assert_eq!(Some(1), program_area[0].codes[1].q_track());
+        assert_eq!(Some(1), program_area[0].codes[1].q_index());
+        assert_eq!(Some(2), program_area[16].codes[1].q_track());
+    }
+
+    #[test]
+    fn test_program_area_without_track_1() {
+        let disc = subcode::SubcodeData::parse(vec![0; 96 * 4]).unwrap();
+        assert!(disc.program_area().is_empty());
+    }
+
+    #[test]
+    fn test_isrc_and_catalog() {
+        let mut disc = two_track_disc();
+        disc.sectors[3] = testing::sector_with_q(testing::catalog_frame("1234567890123", 3));
+        disc.sectors[15] = testing::sector_with_q(testing::isrc_frame("GBXYZ0000001", 15));
+
+        assert_eq!(Some("1234567890123".to_string()), disc.catalog());
+        let tracks = disc.tracks();
+        assert_eq!(None, tracks[0].isrc);
+        assert_eq!(Some("GBXYZ0000001".to_string()), tracks[1].isrc);
+    }
+
+    #[test]
+    fn test_isrc_sector_indices() {
+        let mut disc = two_track_disc();
+        for i in &[2, 5, 14] {
+            disc.sectors[*i].codes[1].data = testing::isrc_frame("USABC9900001", 0);
+        }
+        // A corrupt ISRC frame doesn't count
+        disc.sectors[7].codes[1].data = testing::isrc_frame("USABC9900001", 0);
+        disc.sectors[7].codes[1].data[3] ^= 0xFF;
+
+        assert_eq!(vec![2, 5, 14], disc.isrc_sector_indices());
+        assert!(two_track_disc().isrc_sector_indices().is_empty());
+    }
+
+    #[test]
+    fn test_track_index_map() {
+        // Track 1 moves to index 02 at sector 4 and index 03 at sector 7
+        let mut disc = two_track_disc();
+        let start = Msf::new(0, 2, 0).frames();
+        for i in 4..10 {
+            disc.sectors[i].codes[1].data = testing::position_frame(
+                0x0,
+                1,
+                if i < 7 { 2 } else { 3 },
+                Msf::from_frames(i as u32),
+                Msf::from_frames(start + i as u32),
+            );
+        }
+
+        let map = disc.track_index_map();
+        assert_eq!(vec![1, 2], map.keys().cloned().collect::<Vec<_>>());
+        assert_eq!(
+            vec![
+                (1, Msf::new(0, 2, 0)),
+                (2, Msf::new(0, 2, 4)),
+                (3, Msf::new(0, 2, 7)),
+            ],
+            map[&1]
+        );
+        assert_eq!(
+            vec![(0, Msf::new(0, 2, 10)), (1, Msf::new(0, 2, 13))],
+            map[&2]
+        );
+    }
+
+    #[test]
+    fn test_has_leadout_sectors() {
+        assert!(two_track_disc().has_leadout_sectors());
+
+        let mut disc = two_track_disc();
+        disc.sectors.truncate(18);
+        assert!(!disc.has_leadout_sectors());
+    }
+
+    #[test]
+    fn test_pregap_ratio() {
+        // 3 pregap sectors out of 18 in the program area
+        assert_eq!(3.0 / 18.0, two_track_disc().pregap_ratio());
+        assert_eq!(0.0, subcode::SubcodeData::new().pregap_ratio());
+    }
+
+    #[test]
+    fn test_lead_in_points_are_not_tracks() {
+        let mut disc = two_track_disc();
+        let lead_in: Vec<subcode::Sector> = [0xA0, 0xA1, 0xA2, 0x01, 0x02]
+            .iter()
+            .map(|point| testing::sector_with_q(testing::lead_in_frame(0x0, *point, 0, 0, 0)))
+            .collect();
+        disc.sectors.splice(0..0, lead_in);
+
+        let numbers: Vec<u8> = disc.tracks().iter().map(|t| t.number).collect();
+        assert_eq!(vec![1, 2], numbers);
+        assert_eq!(
+            vec![1, 2],
+            disc.track_index_map().keys().cloned().collect::<Vec<_>>()
+        );
+        assert_eq!(5, disc.tracks()[0].start);
+    }
+
+    #[test]
+    fn test_zero_length_tracks() {
+        assert!(two_track_disc().zero_length_tracks().is_empty());
+
+        // Track 3's pregap is recorded as starting before track 2 does
+        let mut disc = DiscBuilder::new()
+            .append_track(1, 0, 10, TrackType::Audio)
+            .append_track(2, 0, 1, TrackType::Audio)
+            .append_track(3, 0, 5, TrackType::Audio)
+            .build();
+        disc.sectors[9].codes[1].data = testing::position_frame(
+            0x0,
+            3,
+            0,
+            Msf::new(0, 0, 2),
+            Msf::from_frames(Msf::new(0, 2, 0).frames() + 9),
+        );
+
+        assert_eq!(vec![2], disc.zero_length_tracks());
+    }
+
+    #[test]
+    fn test_track1_pregap_sectors() {
+        let disc = |start| {
+            DiscBuilder::new()
+                .start(Msf::new(0, 0, 0))
+                .add_track(1, start, 10, TrackType::Audio)
+                .build()
+        };
+        let standard = disc(Msf::new(0, 2, 0));
+        assert_eq!(Some(150), standard.track1_pregap_sectors());
+        assert!(!standard.has_extended_track1_pregap());
+
+        // A three-second pregap, as on a disc with a hidden track
+        let hidden = disc(Msf::new(0, 3, 0));
+        assert_eq!(Some(225), hidden.track1_pregap_sectors());
+        assert!(hidden.has_extended_track1_pregap());
+
+        assert_eq!(None, two_track_disc().track1_pregap_sectors());
+    }
+}
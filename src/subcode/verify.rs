@@ -0,0 +1,416 @@
+use std::ops::Range;
+
+use crate::msf::Msf;
+use crate::subcode::{QRegion, Sector, SubcodeData, SubcodeType};
+
+/// How far, in frames, a track's first index 01 relative time may be from
+/// 00:00:00 before it's considered not to have reset.
+const RELATIVE_RESET_TOLERANCE: u32 = 2;
+
+/// How many times the disc's average Q CRC failure rate a window must
+/// reach to be considered irregular.
+const IRREGULAR_DENSITY_FACTOR: f64 = 4.0;
+
+impl SubcodeData {
+    /// Cross-checks the P and Q channels of every program-area sector.
+    /// The P pause flag should be set exactly when the Q channel reports
+    /// index 00 (a pregap). Returns the indices of sectors where the two
+    /// disagree. Sectors without a CRC-valid ADR-1 frame are skipped.
+    pub fn verify_p_q_consistency(&self) -> Vec<usize> {
+        let mut mismatches = vec![];
+
+        for (i, sector) in self.sectors.iter().enumerate() {
+            let q = sector.channel(SubcodeType::Q);
+            if !q.q_crc_valid() || q.q_region() != Some(QRegion::Program) {
+                continue;
+            }
+            let index = match q.q_index() {
+                Some(index) => index,
+                None => continue,
+            };
+            // Unwrapping is safe since this is always the P channel
+            if sector.channel(SubcodeType::P).p_pause().unwrap() != (index == 0) {
+                mismatches.push(i);
+            }
+        }
+
+        mismatches
+    }
+
+    /// Checks that the Q relative time resets at the start of every track.
+    /// Returns the index of each track's first index 01 sector whose
+    /// relative time is more than a couple of frames past 00:00:00, which
+    /// usually means the dump was assembled from mismatched pieces.
+    pub fn verify_relative_time_resets(&self) -> Vec<usize> {
+        self.tracks()
+            .iter()
+            .map(|track| track.start)
+            .filter(
+                |start| match self.sectors[*start].channel(SubcodeType::Q).q_relative() {
+                    Some(relative) => relative.frames() > RELATIVE_RESET_TOLERANCE,
+                    None => false,
+                },
+            )
+            .collect()
+    }
+
+    /// Flags sectors which look like they were made up by the dumping
+    /// tool: runs of sectors whose only data is a Q frame with a time
+    /// continuing on from the sector before, between sectors which have
+    /// R-W data. Some tools fill in unreadable sectors this way by
+    /// interpolating the Q time and zeroing everything else. Discs without
+    /// R-W data give nothing to compare against, so have no suspects.
+    ///
+    /// Runs at either end of the dump can't be bracketed by real sectors,
+    /// so they're never flagged.
+    pub fn suspected_synthetic_sectors(&self) -> Vec<usize> {
+        let is_bare = |sector: &Sector| {
+            !sector.channel_is_empty(SubcodeType::Q)
+                && sector
+                    .codes
+                    .iter()
+                    .filter(|code| code.channel != SubcodeType::Q)
+                    .all(|code| code.is_empty())
+        };
+        let has_rw = |sector: &Sector| !sector.contains_basic_data_only();
+        let absolute = |i: usize| {
+            let q = self.sectors[i].channel(SubcodeType::Q);
+            if q.q_crc_valid() {
+                q.q_absolute().map(|time| time.frames())
+            } else {
+                None
+            }
+        };
+
+        let mut suspects = vec![];
+        let mut i = 1;
+        while i < self.sectors.len() {
+            if !is_bare(&self.sectors[i]) || !has_rw(&self.sectors[i - 1]) {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < self.sectors.len() && is_bare(&self.sectors[i]) {
+                i += 1;
+            }
+            if i == self.sectors.len() {
+                break;
+            }
+            if !has_rw(&self.sectors[i]) {
+                continue;
+            }
+
+            let base = match absolute(start - 1) {
+                Some(base) => base,
+                None => continue,
+            };
+            let continuous = (start..i).all(|j| absolute(j) == Some(base + (j - start + 1) as u32));
+            if continuous {
+                suspects.extend(start..i);
+            }
+        }
+
+        suspects
+    }
+
+    /// Returns the absolute time sector `index` should have, found by
+    /// counting on from sector 0's absolute time rather than decoding the
+    /// sector's own Q frame. Returns `None` if `index` is past the end of
+    /// the dump, or if sector 0 doesn't have a CRC-valid absolute time.
+    pub fn computed_absolute_time(&self, index: usize) -> Option<Msf> {
+        if index >= self.sectors.len() {
+            return None;
+        }
+        let q = self.sectors[0].channel(SubcodeType::Q);
+        if !q.q_crc_valid() {
+            return None;
+        }
+
+        Some(Msf::from_frames(q.q_absolute()?.frames() + index as u32))
+    }
+
+    /// Returns every sector whose CRC-valid absolute time differs from the
+    /// one given by `computed_absolute_time`, along with the decoded time
+    /// followed by the computed one. Returns nothing if sector 0 has no
+    /// CRC-valid absolute time to count from.
+    pub fn time_discrepancies(&self) -> Vec<(usize, Msf, Msf)> {
+        self.channel_iter(SubcodeType::Q)
+            .enumerate()
+            .filter(|(_, q)| q.q_crc_valid())
+            .filter_map(|(i, q)| {
+                let decoded = q.q_absolute()?;
+                let computed = self.computed_absolute_time(i)?;
+                if decoded != computed {
+                    Some((i, decoded, computed))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Checks that the dump is contiguous: that every sector's absolute
+    /// time is the first sector's plus its index. The first sector's time
+    /// is inferred from the first CRC-valid frame with an absolute time.
+    /// Sectors without a CRC-valid absolute time, such as ADR-2 and ADR-3
+    /// frames and the lead-in, are skipped.
+    ///
+    /// Returns the index of every sector that's out of place. After a
+    /// dropped or duplicated sector, every following sector is reported.
+    pub fn verify_contiguous(&self) -> Result<(), Vec<usize>> {
+        let times: Vec<(usize, i64)> = self
+            .channel_iter(SubcodeType::Q)
+            .enumerate()
+            .filter(|(_, q)| q.q_crc_valid())
+            .filter_map(|(i, q)| {
+                q.q_absolute()
+                    .map(|absolute| (i, i64::from(absolute.frames())))
+            })
+            .collect();
+        let base = match times.first() {
+            Some((i, frames)) => frames - *i as i64,
+            None => return Ok(()),
+        };
+
+        let mismatches: Vec<usize> = times
+            .iter()
+            .filter(|(i, frames)| *frames != base + *i as i64)
+            .map(|(i, _)| *i)
+            .collect();
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(mismatches)
+        }
+    }
+
+    /// Finds regions where Q CRC failures are far more common than on the
+    /// rest of the disc, as with copy protections which deliberately
+    /// corrupt the subcode of a few sectors. Slides a window of `window`
+    /// sectors over the disc, flagging every window whose failure rate is
+    /// at least four times the disc's average. Overlapping flagged windows
+    /// are merged, and each region is trimmed to run from its first
+    /// failing sector to one past its last.
+    ///
+    /// Returns nothing if `window` is 0 or longer than the dump. Damage
+    /// spread evenly across the disc raises the average along with it, so
+    /// it isn't reported.
+    pub fn irregular_subcode_regions(&self, window: usize) -> Vec<Range<usize>> {
+        let failures: Vec<bool> = self
+            .channel_iter(SubcodeType::Q)
+            .map(|q| !q.q_crc_valid())
+            .collect();
+        if window == 0 || window > failures.len() {
+            return vec![];
+        }
+        let total = failures.iter().filter(|failed| **failed).count();
+        let threshold = total as f64 / failures.len() as f64 * IRREGULAR_DENSITY_FACTOR;
+
+        let mut regions: Vec<Range<usize>> = vec![];
+        let mut count = failures[..window].iter().filter(|failed| **failed).count();
+        for start in 0..=failures.len() - window {
+            if start > 0 {
+                count -= failures[start - 1] as usize;
+                count += failures[start + window - 1] as usize;
+            }
+            if count == 0 || (count as f64 / window as f64) < threshold {
+                continue;
+            }
+            match regions.last_mut() {
+                Some(region) if region.end >= start => region.end = start + window,
+                _ => regions.push(start..start + window),
+            }
+        }
+
+        for region in regions.iter_mut() {
+            // Each region holds at least one failure, so both are found
+            let first = (region.start..region.end).find(|i| failures[*i]).unwrap();
+            let last = (region.start..region.end).rfind(|i| failures[*i]).unwrap();
+            *region = first..last + 1;
+        }
+        regions
+    }
+
+    /// Checks that every CRC-valid ADR-2 frame carries the same media
+    /// catalog number. The disc's catalog number is taken to be the one
+    /// found most often, with ties going to whichever appears first.
+    ///
+    /// Returns the index of every sector whose catalog number differs.
+    pub fn catalog_consistency(&self) -> Result<(), Vec<usize>> {
+        let catalogs: Vec<(usize, String)> = self
+            .channel_iter(SubcodeType::Q)
+            .enumerate()
+            .filter(|(_, q)| q.q_crc_valid())
+            .filter_map(|(i, q)| q.q_catalog().map(|catalog| (i, catalog)))
+            .collect();
+
+        let mut counts: Vec<(&str, usize)> = vec![];
+        for (_, catalog) in &catalogs {
+            match counts.iter_mut().find(|(c, _)| c == catalog) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((catalog, 1)),
+            }
+        }
+        // max_by_key returns the last maximum, so search in reverse to
+        // prefer the first
+        let dominant = match counts.iter().rev().max_by_key(|(_, count)| *count) {
+            Some((catalog, _)) => *catalog,
+            None => return Ok(()),
+        };
+
+        let dissenting: Vec<usize> = catalogs
+            .iter()
+            .filter(|(_, catalog)| catalog != dominant)
+            .map(|(i, _)| *i)
+            .collect();
+        if dissenting.is_empty() {
+            Ok(())
+        } else {
+            Err(dissenting)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::msf::Msf;
+    use crate::subcode::testing::{self, position_frame};
+    use crate::subcode::{DiscBuilder, SubcodeData, TrackType};
+
+    fn disc() -> SubcodeData {
+        DiscBuilder::new()
+            .append_track(1, 0, 10, TrackType::Audio)
+            .append_track(2, 4, 10, TrackType::Audio)
+            .lead_out(3)
+            .build()
+    }
+
+    #[test]
+    fn test_consistent_p_and_q() {
+        assert!(disc().verify_p_q_consistency().is_empty());
+    }
+
+    #[test]
+    fn test_mismatched_p_and_q() {
+        let mut disc = disc();
+        // P claims a pause in the middle of track 1
+        disc.sectors[5].codes[0].data = vec![0xFF; 12];
+        assert_eq!(vec![5], disc.verify_p_q_consistency());
+    }
+
+    #[test]
+    fn test_relative_time_resets() {
+        assert!(disc().verify_relative_time_resets().is_empty());
+    }
+
+    #[test]
+    fn test_relative_time_fails_to_reset() {
+        let mut disc = disc();
+        // Track 2 carries on counting from where track 1 left off
+        for i in 0..10 {
+            disc.sectors[14 + i].codes[1].data = position_frame(
+                0,
+                2,
+                1,
+                Msf::from_frames(10 + i as u32),
+                Msf::from_frames(Msf::new(0, 2, 14).frames() + i as u32),
+            );
+        }
+        assert_eq!(vec![14], disc.verify_relative_time_resets());
+    }
+
+    #[test]
+    fn test_contiguous_disc() {
+        assert_eq!(Ok(()), disc().verify_contiguous());
+    }
+
+    #[test]
+    fn test_dropped_sector_is_not_contiguous() {
+        let mut disc = disc();
+        disc.sectors.remove(7);
+        let mismatches = disc.verify_contiguous().unwrap_err();
+        assert_eq!(7, mismatches[0]);
+        assert_eq!(disc.sectors.len() - 7, mismatches.len());
+    }
+
+    #[test]
+    fn test_suspected_synthetic_sectors() {
+        let mut disc = disc();
+        // Give every sector R-W data, except for an interpolated sector
+        for sector in disc.sectors.iter_mut() {
+            sector.codes[2].data[0] = 0x01;
+        }
+        disc.sectors[6].codes[2].data[0] = 0;
+        assert_eq!(vec![6], disc.suspected_synthetic_sectors());
+
+        // A bare sector whose time doesn't follow on isn't interpolated
+        disc.sectors[6].codes[1].data =
+            position_frame(0, 1, 1, Msf::new(0, 0, 50), Msf::new(0, 2, 50));
+        assert!(disc.suspected_synthetic_sectors().is_empty());
+    }
+
+    #[test]
+    fn test_plain_discs_have_no_synthetic_sectors() {
+        assert!(disc().suspected_synthetic_sectors().is_empty());
+    }
+
+    #[test]
+    fn test_irregular_subcode_regions() {
+        let mut disc = DiscBuilder::new()
+            .add_track(1, Msf::new(0, 2, 0), 500, TrackType::Audio)
+            .build();
+        assert!(disc.irregular_subcode_regions(16).is_empty());
+
+        // A burst of bad frames, plus a couple of stray ones elsewhere
+        for i in (200..216).chain(vec![50, 400]) {
+            if i % 3 != 0 {
+                disc.sectors[i].codes[1].data[10] ^= 0xFF;
+            }
+        }
+        assert_eq!(vec![200..216], disc.irregular_subcode_regions(16));
+        assert!(disc.irregular_subcode_regions(0).is_empty());
+        assert!(disc.irregular_subcode_regions(501).is_empty());
+    }
+
+    #[test]
+    fn test_catalog_consistency() {
+        let mut disc = DiscBuilder::new()
+            .add_track(1, Msf::new(0, 2, 0), 500, TrackType::Audio)
+            .catalog("0123456789012")
+            .build();
+        assert_eq!(Ok(()), disc.catalog_consistency());
+
+        disc.sectors[300].codes[1].data = testing::catalog_frame("9999999999999", 0);
+        assert_eq!(Err(vec![300]), disc.catalog_consistency());
+        assert_eq!(Ok(()), SubcodeData::new().catalog_consistency());
+    }
+
+    #[test]
+    fn test_computed_absolute_time() {
+        let mut disc = disc();
+        for (i, sector) in disc.sectors.iter().enumerate() {
+            assert_eq!(sector.codes[1].q_absolute(), disc.computed_absolute_time(i));
+        }
+        assert_eq!(None, disc.computed_absolute_time(disc.sectors.len()));
+
+        // Corruption elsewhere doesn't matter, but corrupting sector 0 does
+        disc.sectors[5].codes[1].data[7] ^= 0xFF;
+        assert_eq!(Some(Msf::new(0, 2, 5)), disc.computed_absolute_time(5));
+        disc.sectors[0].codes[1].data[7] ^= 0xFF;
+        assert_eq!(None, disc.computed_absolute_time(5));
+    }
+
+    #[test]
+    fn test_time_discrepancies() {
+        let mut disc = disc();
+        assert!(disc.time_discrepancies().is_empty());
+
+        // Sector 10 claims to be one frame later than it is
+        disc.sectors[10].codes[1].data =
+            position_frame(0, 2, 0, Msf::new(0, 0, 4), Msf::new(0, 2, 11));
+        assert_eq!(
+            vec![(10, Msf::new(0, 2, 11), Msf::new(0, 2, 10))],
+            disc.time_discrepancies()
+        );
+    }
+}
@@ -0,0 +1,680 @@
+//! The disc's table of contents: where each track starts and where the
+//! lead-out begins.
+
+#[cfg(feature = "sha1")]
+use sha1::{Digest, Sha1};
+
+use crate::bcd;
+use crate::msf::{Msf, FRAMES_PER_SECOND};
+use crate::subcode::{QControl, QRegion, SubcodeData, SubcodeType};
+
+/// Lead-in POINT giving the first track number and disc type.
+pub const POINT_FIRST_TRACK: u8 = 0xA0;
+/// Lead-in POINT giving the last track number.
+pub const POINT_LAST_TRACK: u8 = 0xA1;
+/// Lead-in POINT giving the start of the lead-out.
+pub const POINT_LEAD_OUT: u8 = 0xA2;
+
+/// Extended lead-in POINT giving the start of the next program area on a
+/// recordable or multisession disc.
+pub const POINT_NEXT_PROGRAM_AREA: u8 = 0xB0;
+/// Extended lead-in POINT giving the recording power and the start of the
+/// first lead-in from the ATIP.
+pub const POINT_ATIP: u8 = 0xC0;
+
+/// ADR of the lead-in frames carrying extended entries.
+pub(crate) const ADR_EXTENDED: u8 = 5;
+
+/// Disc type of a CD-DA or CD-ROM disc, as given by POINT 0xA0.
+pub const DISC_TYPE_CD_DA_OR_CD_ROM: u8 = 0x00;
+/// Disc type of a CD-i disc, as given by POINT 0xA0.
+pub const DISC_TYPE_CD_I: u8 = 0x10;
+/// Disc type of a CD-ROM XA disc, as given by POINT 0xA0.
+pub const DISC_TYPE_CD_ROM_XA: u8 = 0x20;
+
+/// The kind of disc, as given by the disc type in POINT 0xA0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DiscType {
+    /// An audio CD or a plain CD-ROM, which share a disc type.
+    CdDaOrCdRom,
+    /// A CD-i (Green Book) disc.
+    CdI,
+    /// A CD-ROM XA disc.
+    CdRomXa,
+    /// A disc type the Red Book doesn't define, kept as its raw byte.
+    Other(u8),
+}
+
+impl DiscType {
+    /// Decodes a disc type byte from POINT 0xA0.
+    pub fn from_byte(byte: u8) -> DiscType {
+        match byte {
+            DISC_TYPE_CD_DA_OR_CD_ROM => DiscType::CdDaOrCdRom,
+            DISC_TYPE_CD_I => DiscType::CdI,
+            DISC_TYPE_CD_ROM_XA => DiscType::CdRomXa,
+            byte => DiscType::Other(byte),
+        }
+    }
+}
+
+/// A single entry in a table of contents.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "bincode", derive(serde::Serialize, serde::Deserialize))]
+pub enum TocEntry {
+    /// A program track and the absolute time of its index 01.
+    Track {
+        number: u8,
+        control: QControl,
+        start: Msf,
+    },
+    /// The first track number and the disc type (POINT 0xA0).
+    FirstTrack { number: u8, disc_type: u8 },
+    /// The last track number (POINT 0xA1).
+    LastTrack { number: u8 },
+    /// The absolute time at which the lead-out begins (POINT 0xA2).
+    LeadOut { start: Msf },
+    /// An entry from an ADR-5 frame, found on recordable and multisession
+    /// discs, such as POINT 0xB0 or 0xC0. The seven bytes following the
+    /// POINT are kept as-is, since their meaning depends on the POINT.
+    Extended { point: u8, data: [u8; 7] },
+}
+
+/// A table of contents, whether read from the lead-in or supplied from
+/// elsewhere, such as a CloneCD control file.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "bincode", derive(serde::Serialize, serde::Deserialize))]
+pub struct Toc {
+    pub entries: Vec<TocEntry>,
+}
+
+impl Toc {
+    /// Returns the start time of the given track, if the TOC lists it.
+    pub fn track_start(&self, number: u8) -> Option<Msf> {
+        self.entries.iter().find_map(|entry| match *entry {
+            TocEntry::Track {
+                number: n, start, ..
+            } if n == number => Some(start),
+            _ => None,
+        })
+    }
+
+    /// Returns the disc type from the POINT 0xA0 entry, if present.
+    pub fn disc_type(&self) -> Option<u8> {
+        self.entries.iter().find_map(|entry| match *entry {
+            TocEntry::FirstTrack { disc_type, .. } => Some(disc_type),
+            _ => None,
+        })
+    }
+
+    /// Returns the start of the next session's program area from the
+    /// POINT 0xB0 entry, if present and valid. Discs which have been
+    /// closed to further sessions store 0xFF here instead.
+    pub fn next_program_area(&self) -> Option<Msf> {
+        self.entries.iter().find_map(|entry| match *entry {
+            TocEntry::Extended {
+                point: POINT_NEXT_PROGRAM_AREA,
+                data,
+            } => Msf::from_bcd(data[0], data[1], data[2]),
+            _ => None,
+        })
+    }
+
+    /// Returns the start of the lead-out from the POINT 0xA2 entry, if
+    /// present.
+    pub fn lead_out(&self) -> Option<Msf> {
+        self.entries.iter().find_map(|entry| match *entry {
+            TocEntry::LeadOut { start } => Some(start),
+            _ => None,
+        })
+    }
+
+    /// Computes the CDDB (freedb) disc ID from the tracks' start times and
+    /// the lead-out. The first byte is a checksum of the digits of each
+    /// track's start in seconds, the next two the disc's length in
+    /// seconds, and the last the number of tracks.
+    ///
+    /// Returns `None` if the TOC has no tracks or no lead-out.
+    pub fn cddb_disc_id(&self) -> Option<u32> {
+        let starts: Vec<u32> = self
+            .entries
+            .iter()
+            .filter_map(|entry| match *entry {
+                TocEntry::Track { start, .. } => Some(start.frames() / FRAMES_PER_SECOND),
+                _ => None,
+            })
+            .collect();
+        let first = *starts.first()?;
+        let lead_out = self.lead_out()?.frames() / FRAMES_PER_SECOND;
+
+        let digit_sum = |mut n: u32| {
+            let mut sum = 0;
+            while n > 0 {
+                sum += n % 10;
+                n /= 10;
+            }
+            sum
+        };
+        let checksum: u32 = starts.iter().map(|start| digit_sum(*start)).sum();
+        let length = lead_out.saturating_sub(first);
+
+        Some((checksum % 0xFF) << 24 | length << 8 | starts.len() as u32)
+    }
+
+    /// Computes the MusicBrainz disc ID: a SHA-1 digest of the first and
+    /// last track numbers, the lead-out and all 99 possible track starts,
+    /// written as upper case hex and encoded with MusicBrainz's variant of
+    /// base64. Offsets include the two-second lead-in gap, so track 1
+    /// usually starts at 150.
+    ///
+    /// The first and last track numbers come from the POINT 0xA0 and 0xA1
+    /// entries, or from the tracks themselves if those are missing.
+    /// Returns `None` if the TOC has no tracks or no lead-out.
+    #[cfg(feature = "sha1")]
+    pub fn musicbrainz_disc_id(&self) -> Option<String> {
+        let numbers = self.track_numbers();
+        let first = self
+            .first_track()
+            .or_else(|| numbers.iter().cloned().min())?;
+        let last = self
+            .last_track()
+            .or_else(|| numbers.iter().cloned().max())?;
+
+        let mut offsets = [0; 100];
+        offsets[0] = self.lead_out()?.frames();
+        for number in numbers.into_iter().filter(|n| (1..=99).contains(n)) {
+            // Unwrapping is safe since the number came from the TOC
+            offsets[usize::from(number)] = self.track_start(number).unwrap().frames();
+        }
+
+        let mut text = format!("{:02X}{:02X}", first, last);
+        for offset in offsets.iter() {
+            text.push_str(&format!("{:08X}", offset));
+        }
+
+        let mut hasher = Sha1::new();
+        hasher.update(text.as_bytes());
+        let digest: [u8; 20] = hasher.finalize().into();
+        Some(musicbrainz_base64(&digest))
+    }
+
+    /// Returns the first track number from the POINT 0xA0 entry, if
+    /// present.
+    pub fn first_track(&self) -> Option<u8> {
+        self.entries.iter().find_map(|entry| match *entry {
+            TocEntry::FirstTrack { number, .. } => Some(number),
+            _ => None,
+        })
+    }
+
+    /// Returns the last track number from the POINT 0xA1 entry, if
+    /// present.
+    pub fn last_track(&self) -> Option<u8> {
+        self.entries.iter().find_map(|entry| match *entry {
+            TocEntry::LastTrack { number } => Some(number),
+            _ => None,
+        })
+    }
+
+    /// Returns the numbers of every track the TOC lists, in TOC order.
+    pub fn track_numbers(&self) -> Vec<u8> {
+        self.entries
+            .iter()
+            .filter_map(|entry| match *entry {
+                TocEntry::Track { number, .. } => Some(number),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Encodes bytes as base64 using the URL-safe alphabet MusicBrainz uses
+/// for disc IDs, where `.`, `_` and `-` stand in for `+`, `/` and `=`.
+#[cfg(feature = "sha1")]
+fn musicbrainz_base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789._";
+
+    let mut encoded = String::new();
+    for chunk in bytes.chunks(3) {
+        let mut group = [0; 3];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let value = u32::from(group[0]) << 16 | u32::from(group[1]) << 8 | u32::from(group[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(value >> (18 - i * 6)) as usize & 0x3F] as char);
+            } else {
+                encoded.push('-');
+            }
+        }
+    }
+
+    encoded
+}
+
+/// A track whose start in the subcode disagrees with a TOC.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TocMismatch {
+    pub track: u8,
+    /// The start time according to the TOC, or `None` if the TOC doesn't
+    /// list the track.
+    pub expected: Option<Msf>,
+    /// The start time according to the subcode, or `None` if no index 01
+    /// was found for the track.
+    pub found: Option<Msf>,
+}
+
+/// A disagreement between the first and last track numbers given by the
+/// lead-in and the tracks found in the program area.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TrackRangeMismatch {
+    /// The first and last track numbers according to the lead-in.
+    pub expected: (u8, u8),
+    /// The lowest and highest track numbers found in the program area.
+    pub found: (u8, u8),
+}
+
+impl SubcodeData {
+    /// Reads the table of contents from the lead-in's ADR-1 frames, along
+    /// with any ADR-5 frames carrying recordable and multisession
+    /// entries. Each POINT is repeated many times in the lead-in; the
+    /// first CRC-valid copy of each is used. Entries are returned in POINT
+    /// order, so tracks come first, followed by the first track, last
+    /// track and lead-out entries, and finally any extended entries.
+    /// Unrecognised ADR-1 POINTs are skipped.
+    ///
+    /// Returns an empty TOC if the dump doesn't include the lead-in.
+    pub fn parse_toc(&self) -> Toc {
+        let mut points: Vec<((u8, u8), TocEntry)> = vec![];
+
+        for q in self.channel_iter(SubcodeType::Q) {
+            if !q.q_crc_valid() {
+                continue;
+            }
+            let adr = match q.q_adr() {
+                Some(1) if q.q_region() == Some(QRegion::LeadIn) => 1,
+                Some(ADR_EXTENDED) if q.data[1] == 0 => ADR_EXTENDED,
+                _ => continue,
+            };
+            let point = q.data[2];
+            if points.iter().any(|(key, _)| *key == (adr, point)) {
+                continue;
+            }
+            let start = Msf::from_bcd(q.data[7], q.data[8], q.data[9]);
+            let entry = match (adr, point) {
+                (ADR_EXTENDED, _) => {
+                    let mut data = [0; 7];
+                    data.copy_from_slice(&q.data[3..10]);
+                    Some(TocEntry::Extended { point, data })
+                }
+                (_, POINT_FIRST_TRACK) => {
+                    bcd::from_bcd(q.data[7]).map(|number| TocEntry::FirstTrack {
+                        number,
+                        disc_type: q.data[8],
+                    })
+                }
+                (_, POINT_LAST_TRACK) => {
+                    bcd::from_bcd(q.data[7]).map(|number| TocEntry::LastTrack { number })
+                }
+                (_, POINT_LEAD_OUT) => start.map(|start| TocEntry::LeadOut { start }),
+                _ => match (bcd::from_bcd(point), start) {
+                    (Some(number), Some(start)) if number > 0 => Some(TocEntry::Track {
+                        number,
+                        // Unwrapping is safe since we've confirmed this is a Q frame
+                        control: q.q_control().unwrap(),
+                        start,
+                    }),
+                    _ => None,
+                },
+            };
+            if let Some(entry) = entry {
+                points.push(((adr, point), entry));
+            }
+        }
+
+        points.sort_by_key(|(key, _)| *key);
+        Toc {
+            entries: points.into_iter().map(|(_, entry)| entry).collect(),
+        }
+    }
+
+    /// Returns whether the dump includes a lead-in TOC: any CRC-valid
+    /// ADR-1 lead-in frame with a recognised POINT. When it doesn't,
+    /// callers have to fall back on `tracks` to find the disc's layout.
+    pub fn has_toc(&self) -> bool {
+        self.parse_toc()
+            .entries
+            .iter()
+            .any(|entry| !matches!(entry, TocEntry::Extended { .. }))
+    }
+
+    /// Returns whether this is a CD-i (Green Book) disc, according to the
+    /// disc type in the lead-in. Since CD-i data tracks have the same
+    /// control bits as any other data track, this needs the lead-in to
+    /// have been dumped.
+    pub fn is_cdi(&self) -> bool {
+        self.disc_type() == Some(DiscType::CdI)
+    }
+
+    /// Returns the disc type given by the lead-in's POINT 0xA0 entry, or
+    /// `None` if the dump doesn't include it.
+    pub fn disc_type(&self) -> Option<DiscType> {
+        self.parse_toc().disc_type().map(DiscType::from_byte)
+    }
+
+    /// Returns the first and last track numbers given by the lead-in's
+    /// POINT 0xA0 and 0xA1 entries. Returns `None` if either is missing,
+    /// such as when the dump doesn't include the lead-in.
+    pub fn track_number_range(&self) -> Option<(u8, u8)> {
+        let toc = self.parse_toc();
+        Some((toc.first_track()?, toc.last_track()?))
+    }
+
+    /// Checks the lead-in's first and last track numbers against the
+    /// lowest and highest tracks found in the program area. A mismatch
+    /// usually means the dump is missing tracks from either end, or that
+    /// the lead-in describes a different disc. Succeeds if either range
+    /// isn't available.
+    pub fn verify_track_number_range(&self) -> Result<(), TrackRangeMismatch> {
+        let expected = match self.track_number_range() {
+            Some(range) => range,
+            None => return Ok(()),
+        };
+        let numbers: Vec<u8> = self.tracks().iter().map(|track| track.number).collect();
+        let found = match (numbers.iter().min(), numbers.iter().max()) {
+            (Some(first), Some(last)) => (*first, *last),
+            _ => return Ok(()),
+        };
+
+        if expected == found {
+            Ok(())
+        } else {
+            Err(TrackRangeMismatch { expected, found })
+        }
+    }
+
+    /// Compares the start of each track found in the subcode with the
+    /// given TOC, returning every track whose start differs. Tracks listed
+    /// by only one of the two are reported as well, in TOC order followed
+    /// by any tracks missing from the TOC.
+    pub fn validate_against_toc(&self, toc: &Toc) -> Vec<TocMismatch> {
+        let found: Vec<(u8, Option<Msf>)> = self
+            .tracks()
+            .iter()
+            .map(|track| {
+                let start = self.sectors[track.start]
+                    .channel(SubcodeType::Q)
+                    .q_absolute();
+                (track.number, start)
+            })
+            .collect();
+
+        let mut mismatches = vec![];
+        for number in toc.track_numbers() {
+            let expected = toc.track_start(number);
+            let start = found
+                .iter()
+                .find(|(n, _)| *n == number)
+                .and_then(|(_, start)| *start);
+            if start != expected {
+                mismatches.push(TocMismatch {
+                    track: number,
+                    expected,
+                    found: start,
+                });
+            }
+        }
+        for (number, start) in found {
+            if toc.track_start(number).is_none() {
+                mismatches.push(TocMismatch {
+                    track: number,
+                    expected: None,
+                    found: start,
+                });
+            }
+        }
+
+        mismatches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::msf::Msf;
+    use crate::subcode::testing::{self, lead_in_frame, with_crc};
+    use crate::subcode::{DiscBuilder, QControl, SubcodeData, TrackType};
+    use crate::toc::{DiscType, Toc, TocEntry, TocMismatch, TrackRangeMismatch};
+
+    /// A lead-in describing two tracks, with each POINT repeated.
+    fn lead_in(control: u8, disc_type: u8) -> SubcodeData {
+        let frames = [
+            lead_in_frame(control, 0xA0, 0x01, disc_type, 0x00),
+            lead_in_frame(control, 0xA1, 0x02, 0x00, 0x00),
+            lead_in_frame(control, 0xA2, 0x10, 0x00, 0x00),
+            lead_in_frame(control, 0x01, 0x00, 0x02, 0x00),
+            lead_in_frame(0, 0x02, 0x05, 0x30, 0x00),
+        ];
+        SubcodeData {
+            sectors: frames
+                .iter()
+                .cycle()
+                .take(15)
+                .cloned()
+                .map(testing::sector_with_q)
+                .collect(),
+        }
+    }
+
+    fn track(number: u8, start: Msf) -> TocEntry {
+        TocEntry::Track {
+            number,
+            control: QControl::from_nibble(0),
+            start,
+        }
+    }
+
+    /// A twelve-track audio CD.
+    fn sample_toc() -> Toc {
+        let starts = [
+            150, 22767, 41887, 58317, 72102, 91375, 104652, 115380, 132165, 143932, 159870, 174597,
+        ];
+        let mut entries: Vec<TocEntry> = starts
+            .iter()
+            .enumerate()
+            .map(|(i, start)| track(i as u8 + 1, Msf::from_frames(*start)))
+            .collect();
+        entries.push(TocEntry::FirstTrack {
+            number: 1,
+            disc_type: 0,
+        });
+        entries.push(TocEntry::LastTrack { number: 12 });
+        entries.push(TocEntry::LeadOut {
+            start: Msf::from_frames(267257),
+        });
+        Toc { entries }
+    }
+
+    #[test]
+    fn test_cddb_disc_id() {
+        assert_eq!(Some(0xA70D_E90C), sample_toc().cddb_disc_id());
+        assert_eq!(None, Toc::default().cddb_disc_id());
+    }
+
+    #[cfg(feature = "sha1")]
+    #[test]
+    fn test_musicbrainz_disc_id() {
+        assert_eq!(
+            Some("I5l9cCSFccLKFEKS.7wqSZAorPU-".to_string()),
+            sample_toc().musicbrainz_disc_id()
+        );
+        assert_eq!(None, Toc::default().musicbrainz_disc_id());
+    }
+
+    #[test]
+    fn test_track_number_range() {
+        let disc = |tracks: u8| {
+            let mut builder = DiscBuilder::new();
+            for number in 1..=tracks {
+                let start = Msf::from_frames(150 + u32::from(number - 1) * 100);
+                builder = builder.add_track(number, start, 100, TrackType::Audio);
+            }
+            let mut disc = lead_in(0, 0);
+            disc.sectors.extend(builder.build().sectors);
+            disc
+        };
+
+        assert_eq!(Some((1, 2)), disc(2).track_number_range());
+        assert_eq!(Ok(()), disc(2).verify_track_number_range());
+        // The lead-in's POINT 0xA1 claims two tracks, but there are three
+        assert_eq!(
+            Err(TrackRangeMismatch {
+                expected: (1, 2),
+                found: (1, 3),
+            }),
+            disc(3).verify_track_number_range()
+        );
+
+        let no_lead_in = DiscBuilder::new()
+            .add_track(1, Msf::new(0, 2, 0), 100, TrackType::Audio)
+            .build();
+        assert_eq!(None, no_lead_in.track_number_range());
+        assert_eq!(Ok(()), no_lead_in.verify_track_number_range());
+    }
+
+    #[test]
+    fn test_validate_against_toc_reports_start_differences() {
+        let disc = DiscBuilder::new()
+            .add_track(1, Msf::new(0, 2, 0), 100, TrackType::Audio)
+            .add_track(2, Msf::new(0, 3, 25), 100, TrackType::Audio)
+            .build();
+
+        let toc = Toc {
+            entries: vec![
+                track(1, Msf::new(0, 2, 0)),
+                track(2, Msf::new(0, 3, 26)),
+                TocEntry::LeadOut {
+                    start: Msf::new(0, 4, 50),
+                },
+            ],
+        };
+        assert_eq!(
+            vec![TocMismatch {
+                track: 2,
+                expected: Some(Msf::new(0, 3, 26)),
+                found: Some(Msf::new(0, 3, 25)),
+            }],
+            disc.validate_against_toc(&toc)
+        );
+
+        let toc = Toc {
+            entries: vec![track(1, Msf::new(0, 2, 0)), track(2, Msf::new(0, 3, 25))],
+        };
+        assert!(disc.validate_against_toc(&toc).is_empty());
+    }
+
+    #[test]
+    fn test_validate_against_toc_reports_missing_tracks() {
+        let disc = DiscBuilder::new()
+            .add_track(1, Msf::new(0, 2, 0), 100, TrackType::Audio)
+            .build();
+
+        let toc = Toc {
+            entries: vec![track(2, Msf::new(0, 3, 25))],
+        };
+        assert_eq!(
+            vec![
+                TocMismatch {
+                    track: 2,
+                    expected: Some(Msf::new(0, 3, 25)),
+                    found: None,
+                },
+                TocMismatch {
+                    track: 1,
+                    expected: None,
+                    found: Some(Msf::new(0, 2, 0)),
+                },
+            ],
+            disc.validate_against_toc(&toc)
+        );
+    }
+
+    #[test]
+    fn test_parse_toc() {
+        let mut disc = lead_in(0x4, 0x00);
+        // A damaged copy of the lead-out POINT is skipped in favour of the next
+        disc.sectors[2].codes[1].data[7] ^= 0xFF;
+
+        let toc = disc.parse_toc();
+        assert_eq!(
+            vec![
+                TocEntry::Track {
+                    number: 1,
+                    control: QControl::from_nibble(0x4),
+                    start: Msf::new(0, 2, 0),
+                },
+                track(2, Msf::new(5, 30, 0)),
+                TocEntry::FirstTrack {
+                    number: 1,
+                    disc_type: 0x00,
+                },
+                TocEntry::LastTrack { number: 2 },
+                TocEntry::LeadOut {
+                    start: Msf::new(10, 0, 0),
+                },
+            ],
+            toc.entries
+        );
+    }
+
+    #[test]
+    fn test_parse_toc_extended_entries() {
+        let mut disc = lead_in(0x4, 0x00);
+        let mut b0 = lead_in_frame(0x4, 0xB0, 0x79, 0x59, 0x74);
+        // Next program area at 12:34:56, with two pointers
+        b0[0] = 0x45;
+        b0[3..7].copy_from_slice(&[0x12, 0x34, 0x56, 0x02]);
+        disc.sectors.push(testing::sector_with_q(with_crc(b0)));
+
+        let toc = disc.parse_toc();
+        assert_eq!(
+            Some(&TocEntry::Extended {
+                point: 0xB0,
+                data: [0x12, 0x34, 0x56, 0x02, 0x79, 0x59, 0x74],
+            }),
+            toc.entries.last()
+        );
+        assert_eq!(Some(Msf::new(12, 34, 56)), toc.next_program_area());
+        assert_eq!(6, toc.entries.len());
+    }
+
+    #[test]
+    fn test_parse_toc_without_lead_in() {
+        let disc = DiscBuilder::new()
+            .add_track(1, Msf::new(0, 2, 0), 10, TrackType::Audio)
+            .build();
+        assert!(disc.parse_toc().entries.is_empty());
+        assert!(!disc.is_cdi());
+    }
+
+    #[test]
+    fn test_has_toc() {
+        assert!(lead_in(0x4, 0x00).has_toc());
+
+        let disc = DiscBuilder::new()
+            .add_track(1, Msf::new(0, 2, 0), 10, TrackType::Audio)
+            .build();
+        assert!(!disc.has_toc());
+    }
+
+    #[test]
+    fn test_is_cdi() {
+        assert!(lead_in(0x4, 0x10).is_cdi());
+        assert!(!lead_in(0x4, 0x20).is_cdi());
+    }
+
+    #[test]
+    fn test_disc_type() {
+        assert_eq!(Some(DiscType::CdRomXa), lead_in(0x4, 0x20).disc_type());
+        assert_eq!(Some(DiscType::CdDaOrCdRom), lead_in(0x0, 0x00).disc_type());
+        assert_eq!(Some(DiscType::Other(0x30)), lead_in(0x4, 0x30).disc_type());
+        assert_eq!(None, SubcodeData::new().disc_type());
+    }
+}